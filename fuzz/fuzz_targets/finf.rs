@@ -0,0 +1,21 @@
+//! Read arbitrary bytes as each FINF message, both as owned data and borrowed from the input.
+
+#![no_main]
+
+use citp::protocol::finf::{Fram, Message, SFra};
+use citp::protocol::{ReadBytes, ReadFromBytes, ReadFromSlice, ReadSlice};
+use libfuzzer_sys::fuzz_target;
+
+fn read<T: ReadFromBytes>(mut data: &[u8]) {
+    let _ = data.read_bytes::<T>();
+}
+
+fn read_slice<'a, T: ReadFromSlice<'a>>(mut data: &'a [u8]) {
+    let _ = data.read_slice::<T>();
+}
+
+fuzz_target!(|data: &[u8]| {
+    read::<Message<SFra>>(data);
+    read::<Message<Fram>>(data);
+    read_slice::<Message<SFra>>(data);
+});
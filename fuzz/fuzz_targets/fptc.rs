@@ -0,0 +1,23 @@
+//! Read arbitrary bytes as each FPTC message, both as owned data and borrowed from the input.
+
+#![no_main]
+
+use citp::protocol::fptc::{Message, Ptch, SPtc, UPtc};
+use citp::protocol::{ReadBytes, ReadFromBytes, ReadFromSlice, ReadSlice};
+use libfuzzer_sys::fuzz_target;
+
+fn read<T: ReadFromBytes>(mut data: &[u8]) {
+    let _ = data.read_bytes::<T>();
+}
+
+fn read_slice<'a, T: ReadFromSlice<'a>>(mut data: &'a [u8]) {
+    let _ = data.read_slice::<T>();
+}
+
+fuzz_target!(|data: &[u8]| {
+    read::<Message<Ptch>>(data);
+    read::<Message<UPtc>>(data);
+    read::<Message<SPtc>>(data);
+    read_slice::<Message<UPtc>>(data);
+    read_slice::<Message<SPtc>>(data);
+});
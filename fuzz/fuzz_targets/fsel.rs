@@ -0,0 +1,22 @@
+//! Read arbitrary bytes as each FSEL message, both as owned data and borrowed from the input.
+
+#![no_main]
+
+use citp::protocol::fsel::{DeSe, Message, Sele};
+use citp::protocol::{ReadBytes, ReadFromBytes, ReadFromSlice, ReadSlice};
+use libfuzzer_sys::fuzz_target;
+
+fn read<T: ReadFromBytes>(mut data: &[u8]) {
+    let _ = data.read_bytes::<T>();
+}
+
+fn read_slice<'a, T: ReadFromSlice<'a>>(mut data: &'a [u8]) {
+    let _ = data.read_slice::<T>();
+}
+
+fuzz_target!(|data: &[u8]| {
+    read::<Message<Sele>>(data);
+    read::<Message<DeSe>>(data);
+    read_slice::<Message<Sele>>(data);
+    read_slice::<Message<DeSe>>(data);
+});
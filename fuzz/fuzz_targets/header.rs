@@ -0,0 +1,21 @@
+//! Read the CITP header and the header of each layer from arbitrary bytes.
+
+#![no_main]
+
+use citp::protocol::{self, ReadBytes, ReadFromBytes};
+use citp::protocol::{finf, fptc, fsel, msex, pinf, sdmx};
+use libfuzzer_sys::fuzz_target;
+
+fn read<T: ReadFromBytes>(mut data: &[u8]) {
+    let _ = data.read_bytes::<T>();
+}
+
+fuzz_target!(|data: &[u8]| {
+    read::<protocol::Header>(data);
+    read::<pinf::Header>(data);
+    read::<sdmx::Header>(data);
+    read::<fptc::Header>(data);
+    read::<fsel::Header>(data);
+    read::<finf::Header>(data);
+    read::<msex::Header>(data);
+});
@@ -0,0 +1,47 @@
+//! Read arbitrary bytes as each MSEX message, both as owned data and, for messages carrying image
+//! buffers, borrowed from the input.
+//!
+//! The layout of each message is determined by the MSEX version within its header, so every
+//! version is reached by the fuzzer.
+
+#![no_main]
+
+use citp::protocol::msex::{
+    CInf, EEIn, ELIn, ELTh, ELUp, EThn, GEIn, GELI, GELT, GETh, GLEI, GVSr, LSta, MEIn, Message,
+    Nack, RqSt, SInf, StFr, VSrc,
+};
+use citp::protocol::{ReadBytes, ReadFromBytes, ReadFromSlice, ReadSlice};
+use libfuzzer_sys::fuzz_target;
+
+fn read<T: ReadFromBytes>(mut data: &[u8]) {
+    let _ = data.read_bytes::<T>();
+}
+
+fn read_slice<'a, T: ReadFromSlice<'a>>(mut data: &'a [u8]) {
+    let _ = data.read_slice::<T>();
+}
+
+fuzz_target!(|data: &[u8]| {
+    read::<Message<CInf>>(data);
+    read::<Message<SInf>>(data);
+    read::<Message<Nack>>(data);
+    read::<Message<LSta>>(data);
+    read::<Message<GELI>>(data);
+    read::<Message<ELIn>>(data);
+    read::<Message<ELUp>>(data);
+    read::<Message<GEIn>>(data);
+    read::<Message<MEIn>>(data);
+    read::<Message<EEIn>>(data);
+    read::<Message<GLEI>>(data);
+    read::<Message<GELT>>(data);
+    read::<Message<ELTh>>(data);
+    read::<Message<GETh>>(data);
+    read::<Message<EThn>>(data);
+    read::<Message<GVSr>>(data);
+    read::<Message<VSrc>>(data);
+    read::<Message<RqSt>>(data);
+    read::<Message<StFr>>(data);
+    read_slice::<Message<ELTh>>(data);
+    read_slice::<Message<EThn>>(data);
+    read_slice::<Message<StFr>>(data);
+});
@@ -0,0 +1,22 @@
+//! Read arbitrary bytes as a **Packet** of any layer, and as a stream of frames whose parts are
+//! reassembled into complete messages.
+
+#![no_main]
+
+use citp::net::{connection, Frame, Reassembler};
+use citp::protocol::{Packet, ReadBytes};
+use libfuzzer_sys::fuzz_target;
+use std::time::Instant;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = { data }.read_bytes::<Packet>();
+
+    let now = Instant::now();
+    let mut reassembler = Reassembler::new();
+    let mut reader = data;
+    while let Ok(frame) = connection::read_frame(&mut reader) {
+        if let Ok(Some(bytes)) = reassembler.insert((), frame.bytes, now) {
+            let _ = Frame::from_bytes(bytes).and_then(|frame| frame.packet());
+        }
+    }
+});
@@ -0,0 +1,16 @@
+//! Read arbitrary bytes as each PINF message.
+
+#![no_main]
+
+use citp::protocol::pinf::{Message, PLoc, PNam};
+use citp::protocol::{ReadBytes, ReadFromBytes};
+use libfuzzer_sys::fuzz_target;
+
+fn read<T: ReadFromBytes>(mut data: &[u8]) {
+    let _ = data.read_bytes::<T>();
+}
+
+fuzz_target!(|data: &[u8]| {
+    read::<Message<PNam>>(data);
+    read::<Message<PLoc>>(data);
+});
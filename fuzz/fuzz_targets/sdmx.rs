@@ -0,0 +1,28 @@
+//! Read arbitrary bytes as each SDMX message, both as owned data and borrowed from the input.
+
+#![no_main]
+
+use citp::protocol::sdmx::{Capa, ChBk, ChLs, EnId, Message, SXSr, Sxus, UNam};
+use citp::protocol::{ReadBytes, ReadFromBytes, ReadFromSlice, ReadSlice};
+use libfuzzer_sys::fuzz_target;
+
+fn read<T: ReadFromBytes>(mut data: &[u8]) {
+    let _ = data.read_bytes::<T>();
+}
+
+fn read_slice<'a, T: ReadFromSlice<'a>>(mut data: &'a [u8]) {
+    let _ = data.read_slice::<T>();
+}
+
+fuzz_target!(|data: &[u8]| {
+    read::<Message<Capa>>(data);
+    read::<Message<UNam>>(data);
+    read::<Message<EnId>>(data);
+    read::<Message<ChBk>>(data);
+    read::<Message<ChLs>>(data);
+    read::<Message<SXSr>>(data);
+    read::<Message<Sxus>>(data);
+    read_slice::<Message<Capa>>(data);
+    read_slice::<Message<ChBk>>(data);
+    read_slice::<Message<ChLs>>(data);
+});
@@ -0,0 +1,127 @@
+//! ## Errors.
+//!
+//! The **WriteToBytes** and **ReadFromBytes** traits return **std::io::Result** so that they may
+//! be used directly with any **Read** or **Write** type. Violations of the protocol are described
+//! by the **Error** type below, which is wrapped within the returned **io::Error**. Use
+//! `Error::from(io_err)` to recover the structured error and tell protocol violations apart from
+//! network failures.
+
+use std::{error, fmt, io};
+
+use crate::protocol::Cookie;
+use crate::protocol::msex::Version;
+
+/// Errors that may occur while reading or writing CITP protocol types.
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error, e.g. the connection was closed or the data ended early.
+    Io(io::Error),
+    /// A cookie did not match the cookie expected at its position.
+    UnexpectedCookie {
+        expected: Cookie,
+        found: Cookie,
+    },
+    /// The message does not support the MSEX version described by its header.
+    UnsupportedVersion(Version),
+    /// The length of a packet does not agree with the `message_size` of its CITP header.
+    LengthMismatch {
+        message_size: u32,
+        length: usize,
+    },
+    /// A string is invalid, e.g. it contains an interior null character.
+    InvalidString(&'static str),
+    /// The number of elements exceeds the maximum that may be described by the count field.
+    CountOverflow {
+        count: usize,
+        max: usize,
+    },
+    /// The message contents are otherwise inconsistent.
+    InvalidMessage(&'static str),
+    /// A string, list or message exceeds the decoding **Limits**.
+    LimitExceeded {
+        limit: &'static str,
+        length: usize,
+        max: usize,
+    },
+}
+
+impl Error {
+    /// Whether or not the error describes a violation of the protocol, rather than an I/O error.
+    pub fn is_protocol_error(&self) -> bool {
+        !matches!(*self, Error::Io(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::UnexpectedCookie { expected, found } => {
+                write!(f, "unexpected cookie \"{}\", expected \"{}\"", found, expected)
+            }
+            Error::UnsupportedVersion(version) => {
+                write!(f, "the message does not support MSEX version {}", version)
+            }
+            Error::LengthMismatch { message_size, length } => write!(
+                f,
+                "the packet length {} does not match the CITP message_size {}",
+                length, message_size,
+            ),
+            Error::InvalidString(reason) => write!(f, "invalid string: {}", reason),
+            Error::CountOverflow { count, max } => write!(
+                f,
+                "the number of elements {} exceeds the maximum possible count {}",
+                count, max,
+            ),
+            Error::InvalidMessage(reason) => write!(f, "invalid message: {}", reason),
+            Error::LimitExceeded { limit, length, max } => {
+                write!(f, "the {} {} exceeds the limit {}", limit, length, max)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = err.into_inner().expect("checked above");
+            return *inner.downcast::<Error>().expect("checked above");
+        }
+        Error::Io(err)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+#[test]
+fn test_error_io_error_round_trip() {
+    let expected = Cookie::new(b"PLoc");
+    let found = Cookie::new(b"PNam");
+    let io_err = io::Error::from(Error::UnexpectedCookie { expected, found });
+    assert_eq!(io_err.kind(), io::ErrorKind::InvalidData);
+    match Error::from(io_err) {
+        Error::UnexpectedCookie { expected: e, found: f } => assert_eq!((e, f), (expected, found)),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    let eof = io::Error::new(io::ErrorKind::UnexpectedEof, "eof");
+    let err = Error::from(eof);
+    assert!(!err.is_protocol_error());
+    assert_eq!(io::Error::from(err).kind(), io::ErrorKind::UnexpectedEof);
+}
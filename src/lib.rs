@@ -1,3 +1,8 @@
 extern crate byteorder;
+extern crate socket2;
 
+pub mod error;
+pub mod net;
 pub mod protocol;
+
+pub use crate::error::Error;
@@ -0,0 +1,319 @@
+//! ## Peer Connections.
+//!
+//! Once a peer has been discovered, messages are exchanged over a TCP connection to the port
+//! advertised within the peer's PLoc message. The **Connection** type reads one complete CITP
+//! packet at a time using the `message_size` field of the CITP header and writes outgoing messages
+//! with their `message_size` filled in. The parts of multi-part messages are reassembled before
+//! they are returned.
+
+use byteorder::ByteOrder;
+use std::ffi::CString;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Instant;
+
+use crate::error::Error;
+use crate::net::{self, Reassembler};
+use crate::protocol::{self, Cookie, LE, Limits, ReadBytes, SizeBytes, WriteBytes, WriteToBytes};
+use crate::protocol::{finf, fptc, fsel, msex, pinf, sdmx};
+
+/// The byte offset of the `message_size` field within the CITP header.
+const MESSAGE_SIZE_OFFSET: usize = 8;
+
+/// A TCP connection with a CITP peer.
+#[derive(Debug)]
+pub struct Connection {
+    stream: TcpStream,
+    reassembler: Reassembler<()>,
+}
+
+/// A single, complete CITP packet read from a connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The CITP header at the start of the packet.
+    pub citp_header: protocol::Header,
+    /// The header of the second layer, determined by the `content_type` of the CITP header.
+    pub layer_header: LayerHeader,
+    /// The raw bytes of the entire packet, including all headers.
+    pub bytes: Vec<u8>,
+}
+
+/// The header of the second layer of a CITP packet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LayerHeader {
+    Pinf(pinf::Header),
+    Sdmx(sdmx::Header),
+    Fptc(fptc::Header),
+    Fsel(fsel::Header),
+    Finf(finf::Header),
+    Msex(msex::Header),
+    /// The CITP `content_type` does not describe a known layer.
+    Unknown,
+}
+
+impl Connection {
+    /// Connect to the peer at the given address, sending a PINF/PNam message with the given name
+    /// as the first message as required by the PINF layer.
+    pub fn connect<A>(addr: A, name: CString) -> io::Result<Self>
+        where
+            A: ToSocketAddrs,
+    {
+        let stream = TcpStream::connect(addr)?;
+        let mut connection = Connection::from_stream(stream);
+        let packet = net::pinf_packet(pinf::PNam { name })?;
+        connection.stream.write_all(&packet)?;
+        Ok(connection)
+    }
+
+    /// Wrap a stream that is already connected, e.g. one accepted by a **TcpListener**.
+    ///
+    /// No messages are sent.
+    pub fn from_stream(stream: TcpStream) -> Self {
+        Connection {
+            stream,
+            reassembler: Reassembler::new(),
+        }
+    }
+
+    /// Read the next complete CITP packet, blocking until it has been received in full.
+    ///
+    /// The parts of multi-part messages are buffered until all parts have been received and are
+    /// returned as a single, reassembled packet.
+    pub fn read_frame(&mut self) -> io::Result<Frame> {
+        loop {
+            let bytes = read_packet(&mut self.stream)?;
+            if let Some(bytes) = self.reassembler.insert((), bytes, Instant::now())? {
+                return Frame::from_bytes(bytes);
+            }
+        }
+    }
+
+    /// Write the given message, filling in the `message_size` of its CITP header.
+    pub fn write_message<T>(&mut self, message: &T) -> io::Result<()>
+        where
+            T: WriteToBytes,
+    {
+        let bytes = encode_message(message)?;
+        self.stream.write_all(&bytes)?;
+        self.stream.flush()
+    }
+
+    /// The address of the remote peer.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// The local address of the connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.local_addr()
+    }
+
+    /// Shut down both halves of the connection.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Both)
+    }
+
+    /// A reference to the inner **TcpStream**, e.g. for configuring timeouts.
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Consume the connection, returning the inner **TcpStream**.
+    pub fn into_stream(self) -> TcpStream {
+        self.stream
+    }
+}
+
+impl Frame {
+    /// Produce a frame from the bytes of a single, complete CITP packet.
+    ///
+    /// The layer header of a part of a multi-part message is only known once the message has been
+    /// reassembled, so it is **LayerHeader::Unknown** for each part.
+    ///
+    /// The `kind` of the CITP header matches that of the layer header, e.g. it is a
+    /// `Kind::InResponseTo` for MSEX responses. The `kind` of a part is read as a
+    /// `Kind::RequestIndex`.
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<Self> {
+        let mut citp_header: protocol::Header = (&bytes[..]).read_bytes()?;
+        let layer_header = match citp_header.message_part_count {
+            0 | 1 => LayerHeader::read_from_packet(&citp_header, &bytes)?,
+            _ => LayerHeader::Unknown,
+        };
+        if let LayerHeader::Msex(ref header) = layer_header {
+            citp_header.kind = header.citp_header.kind;
+        }
+        Ok(Frame { citp_header, layer_header, bytes })
+    }
+
+    /// Read the **Packet** contained within the frame.
+    pub fn packet(&self) -> io::Result<protocol::Packet> {
+        (&self.bytes[..]).read_bytes()
+    }
+
+    /// The content type of the second layer, if known.
+    pub fn layer_content_type(&self) -> Option<Cookie> {
+        match self.layer_header {
+            LayerHeader::Pinf(ref header) => Some(header.content_type),
+            LayerHeader::Sdmx(ref header) => Some(header.content_type),
+            LayerHeader::Fptc(ref header) => Some(header.content_type),
+            LayerHeader::Fsel(ref header) => Some(header.content_type),
+            LayerHeader::Finf(ref header) => Some(header.content_type),
+            LayerHeader::Msex(ref header) => Some(header.content_type),
+            LayerHeader::Unknown => None,
+        }
+    }
+}
+
+impl LayerHeader {
+    /// Read the second layer header from the given packet bytes, using the `content_type` of the
+    /// CITP header to determine the layer.
+    pub fn read_from_packet(citp_header: &protocol::Header, mut bytes: &[u8]) -> io::Result<Self> {
+        let header = match citp_header.content_type {
+            ct if ct == pinf::Header::CONTENT_TYPE => LayerHeader::Pinf(bytes.read_bytes()?),
+            ct if ct == sdmx::Header::CONTENT_TYPE => LayerHeader::Sdmx(bytes.read_bytes()?),
+            ct if ct == fptc::Header::CONTENT_TYPE => LayerHeader::Fptc(bytes.read_bytes()?),
+            ct if ct == fsel::Header::CONTENT_TYPE => LayerHeader::Fsel(bytes.read_bytes()?),
+            ct if ct == finf::Header::CONTENT_TYPE => LayerHeader::Finf(bytes.read_bytes()?),
+            ct if ct == msex::Header::CONTENT_TYPE => LayerHeader::Msex(bytes.read_bytes()?),
+            _ => LayerHeader::Unknown,
+        };
+        Ok(header)
+    }
+}
+
+/// Read a single, complete CITP packet from the given reader.
+///
+/// The CITP header is read first and its `message_size` is used to determine how many more bytes
+/// belong to the packet. Parts of multi-part messages are returned as they are read; see the
+/// **Reassembler**.
+pub fn read_frame<R>(reader: R) -> io::Result<Frame>
+    where
+        R: Read,
+{
+    Frame::from_bytes(read_packet(reader)?)
+}
+
+/// Read the bytes of a single CITP packet from the given reader.
+fn read_packet<R>(mut reader: R) -> io::Result<Vec<u8>>
+    where
+        R: Read,
+{
+    let mut header_bytes = [0u8; net::HEADER_SIZE];
+    reader.read_exact(&mut header_bytes)?;
+    let message_size = read_message_size(&header_bytes)?;
+    let mut bytes = Vec::with_capacity(message_size);
+    bytes.extend_from_slice(&header_bytes);
+    bytes.resize(message_size, 0);
+    reader.read_exact(&mut bytes[header_bytes.len()..])?;
+    Ok(bytes)
+}
+
+/// Read the CITP header at the start of the given bytes, returning the `message_size` of the
+/// packet once it has been checked against the current **Limits**.
+pub(crate) fn read_message_size(mut bytes: &[u8]) -> io::Result<usize> {
+    let citp_header: protocol::Header = bytes.read_bytes()?;
+    protocol::check_cookie(citp_header.cookie, protocol::Header::COOKIE)?;
+    Limits::current().check_message_size(citp_header.message_size)?;
+    let message_size = citp_header.message_size as usize;
+    if message_size < citp_header.size_bytes() {
+        let length = citp_header.size_bytes();
+        let message_size = citp_header.message_size;
+        return Err(Error::LengthMismatch { message_size, length }.into());
+    }
+    Ok(message_size)
+}
+
+/// Write the given message to bytes, filling in the `message_size` of its CITP header with the
+/// total number of bytes written.
+pub fn encode_message<T>(message: &T) -> io::Result<Vec<u8>>
+    where
+        T: WriteToBytes,
+{
+    let mut bytes = Vec::new();
+    bytes.write_bytes(message)?;
+    if bytes.len() < MESSAGE_SIZE_OFFSET + 4 {
+        let err_msg = "message is too small to contain a CITP header";
+        return Err(Error::InvalidMessage(err_msg).into());
+    }
+    let message_size = bytes.len() as u32;
+    LE::write_u32(&mut bytes[MESSAGE_SIZE_OFFSET..MESSAGE_SIZE_OFFSET + 4], message_size);
+    Ok(bytes)
+}
+
+#[test]
+fn test_connection_pnam_first_and_framing() {
+    use std::net::TcpListener;
+    use std::thread;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = thread::spawn(move || {
+        let mut connection = Connection::connect(addr, CString::new("Console").unwrap()).unwrap();
+        let mut message = pinf::Message::new(pinf::PLoc {
+            listening_tcp_port: 0,
+            kind: CString::new("LightingConsole").unwrap(),
+            name: CString::new("Console").unwrap(),
+            state: CString::new("Idle").unwrap(),
+        });
+        // Clear the message size to check that it is filled in when writing.
+        message.pinf_header.citp_header.message_size = 0;
+        connection.write_message(&message).unwrap();
+        // Send the message again split into multiple parts.
+        let bytes = encode_message(&message).unwrap();
+        let mut stream = connection.stream();
+        for part in net::fragment(&bytes, 32).unwrap() {
+            stream.write_all(&part).unwrap();
+        }
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    let mut connection = Connection::from_stream(stream);
+
+    let pnam = connection.read_frame().unwrap();
+    assert_eq!(pnam.layer_content_type().unwrap(), pinf::PNam::CONTENT_TYPE);
+    let name = (&pnam.bytes[24..]).read_bytes::<pinf::PNam>().unwrap().name;
+    assert_eq!(name.to_str().unwrap(), "Console");
+
+    let ploc = connection.read_frame().unwrap();
+    assert_eq!(ploc.citp_header.message_size as usize, ploc.bytes.len());
+    match ploc.packet().unwrap() {
+        protocol::Packet::PLoc(msg) => assert_eq!(msg.message.state.to_str().unwrap(), "Idle"),
+        packet => panic!("expected a PLoc packet, found {:?}", packet),
+    }
+    match ploc.layer_header {
+        LayerHeader::Pinf(header) => {
+            assert_eq!(header.content_type, pinf::PLoc::CONTENT_TYPE);
+        }
+        _ => panic!("expected a PINF header"),
+    }
+
+    let reassembled = connection.read_frame().unwrap();
+    assert_eq!(reassembled, ploc);
+    client.join().unwrap();
+}
+
+#[test]
+fn test_frame_response_kind() {
+    use std::borrow::Cow;
+    use std::time::Instant;
+
+    let mut requests = msex::Requests::new();
+    let request = msex::Message::request(msex::Version::V1_2, msex::GVSr, &mut requests);
+    let vsrc = msex::VSrc { sources: Cow::Owned(vec![]) };
+    let response = msex::Message::response(msex::Version::V1_2, vsrc, &request.msex_header);
+    let bytes = encode_message(&response).unwrap();
+
+    // The kind of the CITP header is classified by the MSEX header, including once reassembled.
+    let mut reassembler = Reassembler::new();
+    let mut reassembled = None;
+    for part in net::fragment(&bytes, 24).unwrap() {
+        let frame = Frame::from_bytes(part.clone()).unwrap();
+        assert_eq!(frame.citp_header.kind, request.msex_header.citp_header.kind);
+        reassembled = reassembler.insert((), part, Instant::now()).unwrap();
+    }
+    for bytes in [bytes, reassembled.unwrap()] {
+        let frame = Frame::from_bytes(bytes).unwrap();
+        assert_eq!(frame.citp_header.kind, response.msex_header.citp_header.kind);
+        assert_eq!(frame.layer_header, LayerHeader::Msex(response.msex_header));
+    }
+}
@@ -0,0 +1,628 @@
+//! ## Peer Discovery.
+//!
+//! CITP peers announce their location by multicasting a PINF/PLoc message to
+//! `pinf::MULTICAST_ADDR` on `pinf::MULTICAST_PORT`. The **Discovery** service joins the multicast
+//! group, periodically announces our own **PLoc** and maintains a table of the peers that have
+//! been heard from, removing those that have not been heard from within the peer timeout.
+//!
+//! Early implementations of CITP instead broadcast a PINF/PNam message on
+//! `pinf::OLD_BROADCAST_PORT`. When enabled via **Config::legacy_broadcast**, the service also
+//! listens for and broadcasts these messages, merging legacy peers into the same peer table.
+
+use std::collections::hash_map::{self, HashMap};
+use std::ffi::CString;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::net;
+use crate::protocol::{ReadBytes, pinf};
+
+/// The default interval at which our own **PLoc** message is announced.
+pub const DEFAULT_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The default duration after which a peer that has not been heard from is removed.
+pub const DEFAULT_PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximum size of a UDP datagram payload.
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// Configuration for the **Discovery** service.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Config {
+    /// The interval at which our own **PLoc** message is announced.
+    pub announce_interval: Duration,
+    /// The duration after which a peer that has not been heard from is removed.
+    pub peer_timeout: Duration,
+    /// The address of the interface on which multicast groups are joined and sent to.
+    /// `Ipv4Addr::UNSPECIFIED` lets the OS choose.
+    pub interface: Ipv4Addr,
+    /// Whether or not to also join and announce on `pinf::OLD_MULTICAST_ADDR`, used by peers that
+    /// predate early 2014.
+    pub old_multicast_addr: bool,
+    /// Whether or not to also listen for and broadcast PINF/PNam messages on
+    /// `pinf::OLD_BROADCAST_PORT`, used by peers that predate PLoc multicasting.
+    pub legacy_broadcast: bool,
+}
+
+/// A service that announces our own location and discovers the location of other peers.
+///
+/// The service does not spawn any threads. Instead, **Discovery::update** should be called
+/// regularly (e.g. once per frame or once per iteration of a network loop) to process received
+/// messages, announce our own **PLoc** when due and remove stale peers.
+#[derive(Debug)]
+pub struct Discovery {
+    socket: UdpSocket,
+    legacy_socket: Option<UdpSocket>,
+    sender: UdpSocket,
+    state: State,
+}
+
+/// A peer that has been discovered on the network.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Peer {
+    /// The address from which the peer's most recent **PLoc** was sent, or its most recent
+    /// **PNam** for legacy peers.
+    pub addr: SocketAddr,
+    /// The port on which the peer is listening for incoming TCP connections. `0` if not listening.
+    pub listening_tcp_port: u16,
+    /// Can be "LightingConsole", "MediaServer" or "Visualiser".
+    pub kind: CString,
+    /// The display name of the peer.
+    pub name: CString,
+    /// The display state of the peer, e.g. "Idle", "Running".
+    pub state: CString,
+    /// The moment at which the peer was last heard from.
+    pub last_seen: Instant,
+    /// Whether the peer has only been heard from via legacy PNam broadcasts. Legacy peers do not
+    /// describe their kind, state or TCP port, so these fields are left empty.
+    pub legacy: bool,
+}
+
+/// The table of discovered peers, keyed by their IP address and listening TCP port.
+///
+/// Peers are not keyed by the port from which they announce themselves, as announcements are sent
+/// from ephemeral ports. This allows multiple peers on a single host to be told apart by the port
+/// on which they listen, while a peer that restarts replaces its previous entry. Legacy peers do
+/// not describe a TCP port, so they are keyed by their IP address alone and are merged into the
+/// peers of the same host that are known via **PLoc**.
+#[derive(Clone, Debug, Default)]
+pub struct Peers {
+    map: HashMap<(IpAddr, u16), Peer>,
+}
+
+/// The state of a discovery service, shared by **Discovery** and its async equivalent which only
+/// differ in how they wait upon their sockets.
+#[derive(Debug)]
+pub(crate) struct State {
+    config: Config,
+    ploc: pinf::PLoc,
+    sender_port: u16,
+    peers: Peers,
+    last_announced: Option<Instant>,
+    announcement: Vec<u8>,
+    legacy_announcement: Vec<u8>,
+    buffer: Vec<u8>,
+}
+
+/// The sockets used by the discovery service.
+pub(crate) struct Sockets {
+    /// Receives multicast PLoc announcements.
+    pub(crate) socket: UdpSocket,
+    /// Receives legacy PNam broadcasts, if enabled.
+    pub(crate) legacy_socket: Option<UdpSocket>,
+    /// Sends our own announcements.
+    pub(crate) sender: UdpSocket,
+}
+
+/// A peer announcement read from a received datagram.
+enum Announcement {
+    PLoc(pinf::PLoc),
+    PNam(pinf::PNam),
+}
+
+/// An iterator yielding all peers within the **Peers** table.
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    iter: hash_map::Values<'a, (IpAddr, u16), Peer>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            announce_interval: DEFAULT_ANNOUNCE_INTERVAL,
+            peer_timeout: DEFAULT_PEER_TIMEOUT,
+            interface: Ipv4Addr::UNSPECIFIED,
+            old_multicast_addr: false,
+            legacy_broadcast: false,
+        }
+    }
+}
+
+impl Discovery {
+    /// Begin discovering peers using the default **Config**, announcing ourselves with the given
+    /// **PLoc**.
+    pub fn new(ploc: pinf::PLoc) -> io::Result<Self> {
+        Self::with_config(ploc, Config::default())
+    }
+
+    /// Begin discovering peers using the given **Config**, announcing ourselves with the given
+    /// **PLoc**.
+    pub fn with_config(ploc: pinf::PLoc, config: Config) -> io::Result<Self> {
+        let Sockets { socket, legacy_socket, sender } = bind_sockets(&config)?;
+        let state = State::new(ploc, config, sender.local_addr()?.port());
+        Ok(Discovery { socket, legacy_socket, sender, state })
+    }
+
+    /// The configuration with which the service was created.
+    pub fn config(&self) -> &Config {
+        self.state.config()
+    }
+
+    /// The **PLoc** with which we announce ourselves.
+    pub fn ploc(&self) -> &pinf::PLoc {
+        self.state.ploc()
+    }
+
+    /// Change the **PLoc** with which we announce ourselves, e.g. to update our display state.
+    ///
+    /// The new **PLoc** is announced upon the next call to **Discovery::update**.
+    pub fn set_ploc(&mut self, ploc: pinf::PLoc) {
+        self.state.set_ploc(ploc);
+    }
+
+    /// The table of currently known peers.
+    pub fn peers(&self) -> &Peers {
+        self.state.peers()
+    }
+
+    /// Immediately multicast our **PLoc**.
+    ///
+    /// If **Config::legacy_broadcast** is enabled, a **PNam** with our name is also broadcast.
+    pub fn announce(&mut self) -> io::Result<()> {
+        for (datagram, addr) in self.state.announcements()? {
+            self.sender.send_to(datagram, addr)?;
+        }
+        self.state.announced(Instant::now());
+        Ok(())
+    }
+
+    /// Process all pending messages, announce our **PLoc** if the announce interval has elapsed
+    /// and remove all peers that have not been heard from within the peer timeout.
+    ///
+    /// Returns the peers that were removed.
+    pub fn update(&mut self) -> io::Result<Vec<Peer>> {
+        self.receive()?;
+        let now = Instant::now();
+        if now >= self.state.next_announcement(now) {
+            self.announce()?;
+        }
+        Ok(self.state.remove_expired(now))
+    }
+
+    /// Read all datagrams that are pending on the sockets without blocking.
+    fn receive(&mut self) -> io::Result<()> {
+        let Discovery { ref socket, ref legacy_socket, ref mut state, .. } = *self;
+        for socket in Some(socket).into_iter().chain(legacy_socket) {
+            state.receive_pending(|buffer| socket.recv_from(buffer))?;
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    /// The initial state of a service announcing the given **PLoc** from the given sender port.
+    pub(crate) fn new(ploc: pinf::PLoc, config: Config, sender_port: u16) -> Self {
+        State {
+            config,
+            ploc,
+            sender_port,
+            peers: Peers::default(),
+            last_announced: None,
+            announcement: Vec::new(),
+            legacy_announcement: Vec::new(),
+            buffer: vec![0; MAX_DATAGRAM_SIZE],
+        }
+    }
+
+    /// The configuration with which the service was created.
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The **PLoc** with which we announce ourselves.
+    pub(crate) fn ploc(&self) -> &pinf::PLoc {
+        &self.ploc
+    }
+
+    /// The table of currently known peers.
+    pub(crate) fn peers(&self) -> &Peers {
+        &self.peers
+    }
+
+    /// Change our **PLoc**, marking it as due to be announced.
+    pub(crate) fn set_ploc(&mut self, ploc: pinf::PLoc) {
+        self.ploc = ploc;
+        self.last_announced = None;
+    }
+
+    /// The moment at which our **PLoc** is next due to be announced, or `now` if we have not yet
+    /// announced ourselves.
+    pub(crate) fn next_announcement(&self, now: Instant) -> Instant {
+        match self.last_announced {
+            None => now,
+            Some(last) => last + self.config.announce_interval,
+        }
+    }
+
+    /// Encode our announcements, producing each datagram along with the address to send it to.
+    ///
+    /// The encoded announcements are retained so that they may be recognised when looped back.
+    pub(crate) fn announcements(&mut self) -> io::Result<Vec<(&[u8], SocketAddrV4)>> {
+        self.announcement = net::pinf_packet(&self.ploc)?;
+        if self.config.legacy_broadcast {
+            let pnam = pinf::PNam { name: self.ploc.name.clone() };
+            self.legacy_announcement = net::pinf_packet(&pnam)?;
+        }
+        let announcement = &self.announcement[..];
+        let mut datagrams: Vec<_> = multicast_addrs(&self.config)
+            .into_iter()
+            .map(|group| (announcement, SocketAddrV4::new(group, pinf::MULTICAST_PORT)))
+            .collect();
+        if self.config.legacy_broadcast {
+            let addr = SocketAddrV4::new(Ipv4Addr::BROADCAST, pinf::OLD_BROADCAST_PORT);
+            datagrams.push((&self.legacy_announcement[..], addr));
+        }
+        Ok(datagrams)
+    }
+
+    /// Record that our announcements were sent at the given moment.
+    pub(crate) fn announced(&mut self, now: Instant) {
+        self.last_announced = Some(now);
+    }
+
+    /// Receive datagrams via `recv_from` until it would block, updating the peer table with each
+    /// announcement.
+    ///
+    /// Datagrams sent from our sender port that match one of our own announcements are ignored, as
+    /// multicast messages are looped back to the sender.
+    pub(crate) fn receive_pending<F>(&mut self, mut recv_from: F) -> io::Result<()>
+        where
+            F: FnMut(&mut [u8]) -> io::Result<(usize, SocketAddr)>,
+    {
+        loop {
+            let (len, addr) = match recv_from(&mut self.buffer) {
+                Ok(received) => received,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            };
+            let bytes = &self.buffer[..len];
+            // Ignore our own announcements looped back to us.
+            let own = [&self.announcement[..], &self.legacy_announcement[..]];
+            if addr.port() == self.sender_port && own.contains(&bytes) {
+                continue;
+            }
+            match read_announcement(bytes) {
+                Some(Announcement::PLoc(ploc)) => {
+                    self.peers.update_ploc(addr, ploc, Instant::now());
+                }
+                Some(Announcement::PNam(pnam)) => {
+                    self.peers.update_pnam(addr, pnam, Instant::now());
+                }
+                None => (),
+            }
+        }
+    }
+
+    /// Remove all peers that have not been heard from within the peer timeout of `now`.
+    pub(crate) fn remove_expired(&mut self, now: Instant) -> Vec<Peer> {
+        self.peers.remove_expired(now, self.config.peer_timeout)
+    }
+}
+
+impl Peer {
+    /// The address on which the peer is listening for incoming TCP connections, if any.
+    pub fn tcp_addr(&self) -> Option<SocketAddr> {
+        match self.listening_tcp_port {
+            0 => None,
+            port => Some(SocketAddr::new(self.addr.ip(), port)),
+        }
+    }
+}
+
+impl Peers {
+    /// Update the table with a **PLoc** received from the given address at the given moment.
+    ///
+    /// A legacy peer with the same IP address is replaced, as the host is now known via **PLoc**.
+    ///
+    /// Returns `true` if the peer was not previously known.
+    pub fn update_ploc(&mut self, addr: SocketAddr, ploc: pinf::PLoc, now: Instant) -> bool {
+        let legacy_key = (addr.ip(), 0);
+        let was_legacy = matches!(self.map.get(&legacy_key), Some(peer) if peer.legacy);
+        if was_legacy {
+            self.map.remove(&legacy_key);
+        }
+        let pinf::PLoc { listening_tcp_port, kind, name, state } = ploc;
+        let peer = Peer {
+            addr,
+            listening_tcp_port,
+            kind,
+            name,
+            state,
+            last_seen: now,
+            legacy: false,
+        };
+        let was_known = self.map.insert((addr.ip(), listening_tcp_port), peer).is_some();
+        !was_known && !was_legacy
+    }
+
+    /// Update the table with a legacy **PNam** broadcast received from the given address at the
+    /// given moment.
+    ///
+    /// If peers with the same IP address are already known, the name and last seen moment of the
+    /// one announcing from the same address are updated, or of the only one if there is no such
+    /// peer. If several peers on the host are known via **PLoc** and none announce from the same
+    /// address, the **PNam** cannot be attributed to any of them and is ignored. Otherwise the
+    /// peer is added as a legacy peer.
+    ///
+    /// Returns `true` if the peer was not previously known.
+    pub fn update_pnam(&mut self, addr: SocketAddr, pnam: pinf::PNam, now: Instant) -> bool {
+        let ip = addr.ip();
+        let mut host_peers: Vec<&mut Peer> =
+            self.map.values_mut().filter(|peer| peer.addr.ip() == ip).collect();
+        let index = match host_peers.iter().position(|peer| peer.addr == addr) {
+            Some(index) => index,
+            None if host_peers.len() == 1 => 0,
+            None if host_peers.len() > 1 => return false,
+            None => {
+                let peer = Peer {
+                    addr,
+                    listening_tcp_port: 0,
+                    kind: CString::default(),
+                    name: pnam.name,
+                    state: CString::default(),
+                    last_seen: now,
+                    legacy: true,
+                };
+                self.map.insert((ip, 0), peer);
+                return true;
+            }
+        };
+        let peer = &mut host_peers[index];
+        if peer.legacy {
+            peer.addr = addr;
+        }
+        peer.name = pnam.name;
+        peer.last_seen = now;
+        false
+    }
+
+    /// Remove all peers that have not been heard from within `timeout` of `now`.
+    ///
+    /// Returns the peers that were removed.
+    pub fn remove_expired(&mut self, now: Instant, timeout: Duration) -> Vec<Peer> {
+        let expired: Vec<(IpAddr, u16)> = self
+            .map
+            .iter()
+            .filter(|(_, peer)| now.duration_since(peer.last_seen) > timeout)
+            .map(|(&key, _)| key)
+            .collect();
+        expired
+            .iter()
+            .filter_map(|key| self.map.remove(key))
+            .collect()
+    }
+
+    /// The peer at the given IP address that listens for TCP connections on the given port.
+    ///
+    /// Legacy peers and peers that are not listening are found with a `listening_tcp_port` of `0`.
+    pub fn get(&self, ip: IpAddr, listening_tcp_port: u16) -> Option<&Peer> {
+        self.map.get(&(ip, listening_tcp_port))
+    }
+
+    /// The number of known peers.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether or not there are no known peers.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// An iterator yielding all known peers in no particular order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { iter: self.map.values() }
+    }
+}
+
+impl<'a> IntoIterator for &'a Peers {
+    type Item = &'a Peer;
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Peer;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Bind the non-blocking sockets used for discovery with the given **Config**.
+pub(crate) fn bind_sockets(config: &Config) -> io::Result<Sockets> {
+    let socket = net::bind_reusable_udp(pinf::MULTICAST_PORT)?;
+    for group in multicast_addrs(config) {
+        socket.join_multicast_v4(&group, &config.interface)?;
+    }
+    let legacy_socket = if config.legacy_broadcast {
+        Some(net::bind_reusable_udp(pinf::OLD_BROADCAST_PORT)?)
+    } else {
+        None
+    };
+    let sender = UdpSocket::bind(SocketAddrV4::new(config.interface, 0))?;
+    sender.set_broadcast(config.legacy_broadcast)?;
+    if !config.interface.is_unspecified() {
+        let sender = socket2::SockRef::from(&sender);
+        sender.set_multicast_if_v4(&config.interface)?;
+    }
+    Ok(Sockets { socket, legacy_socket, sender })
+}
+
+/// The multicast groups on which to listen and announce.
+fn multicast_addrs(config: &Config) -> Vec<Ipv4Addr> {
+    let mut addrs = vec![Ipv4Addr::from(pinf::MULTICAST_ADDR)];
+    if config.old_multicast_addr {
+        addrs.push(Ipv4Addr::from(pinf::OLD_MULTICAST_ADDR));
+    }
+    addrs
+}
+
+/// Attempt to read a PINF/PLoc or PINF/PNam message from the given datagram.
+///
+/// Returns `None` if the datagram does not contain a valid PLoc or PNam message.
+fn read_announcement(mut bytes: &[u8]) -> Option<Announcement> {
+    let header = bytes.read_bytes::<pinf::Header>().ok()?;
+    if !net::is_pinf(&header) {
+        return None;
+    }
+    if header.content_type == pinf::PLoc::CONTENT_TYPE {
+        bytes.read_bytes().ok().map(Announcement::PLoc)
+    } else if header.content_type == pinf::PNam::CONTENT_TYPE {
+        bytes.read_bytes().ok().map(Announcement::PNam)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_peers_update_and_expire() {
+    use byteorder::{ByteOrder, LE};
+
+    let ploc = pinf::PLoc {
+        listening_tcp_port: 6436,
+        kind: CString::new("MediaServer").unwrap(),
+        name: CString::new("Server").unwrap(),
+        state: CString::new("Running").unwrap(),
+    };
+    let packet = net::pinf_packet(&ploc).unwrap();
+    assert_eq!(LE::read_u32(&packet[8..12]) as usize, packet.len());
+    let read = match read_announcement(&packet) {
+        Some(Announcement::PLoc(read)) => read,
+        _ => panic!("expected PLoc"),
+    };
+    assert_eq!(read, ploc);
+
+    let start = Instant::now();
+    let a: SocketAddr = "192.168.0.10:50000".parse().unwrap();
+    let b: SocketAddr = "192.168.0.11:50000".parse().unwrap();
+    let mut peers = Peers::default();
+    assert!(peers.update_ploc(a, read.clone(), start));
+    assert!(peers.update_ploc(b, read.clone(), start));
+    assert!(!peers.update_ploc(b, read, start + Duration::from_secs(5)));
+    assert_eq!(peers.len(), 2);
+    let peer = peers.get(a.ip(), 6436).unwrap();
+    assert_eq!(peer.tcp_addr(), Some("192.168.0.10:6436".parse().unwrap()));
+
+    let removed = peers.remove_expired(start + Duration::from_secs(11), DEFAULT_PEER_TIMEOUT);
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].addr, a);
+    assert_eq!(peers.iter().map(|peer| peer.addr).collect::<Vec<_>>(), vec![b]);
+}
+
+#[test]
+fn test_peers_legacy_pnam() {
+    let pnam = pinf::PNam { name: CString::new("Visualiser").unwrap() };
+    let packet = net::pinf_packet(&pnam).unwrap();
+    let read = match read_announcement(&packet) {
+        Some(Announcement::PNam(read)) => read,
+        _ => panic!("expected PNam"),
+    };
+    assert_eq!(read, pnam);
+
+    let now = Instant::now();
+    let legacy: SocketAddr = "192.168.0.20:4810".parse().unwrap();
+    let multicast: SocketAddr = "192.168.0.21:50000".parse().unwrap();
+    let ploc = pinf::PLoc {
+        listening_tcp_port: 6436,
+        kind: CString::new("MediaServer").unwrap(),
+        name: CString::new("Server").unwrap(),
+        state: CString::new("Idle").unwrap(),
+    };
+    let mut peers = Peers::default();
+    assert!(peers.update_pnam(legacy, read, now));
+    assert!(peers.update_ploc(multicast, ploc, now));
+    let renamed = pinf::PNam { name: CString::new("Server B").unwrap() };
+    assert!(!peers.update_pnam(multicast, renamed, now));
+
+    let legacy_peer = peers.get(legacy.ip(), 0).unwrap();
+    assert!(legacy_peer.legacy);
+    assert_eq!(legacy_peer.tcp_addr(), None);
+    let multicast_peer = peers.get(multicast.ip(), 6436).unwrap();
+    assert!(!multicast_peer.legacy);
+    assert_eq!(multicast_peer.name.to_str().unwrap(), "Server B");
+    assert_eq!(multicast_peer.listening_tcp_port, 6436);
+}
+
+#[test]
+fn test_peers_merge_by_host() {
+    let now = Instant::now();
+    let multicast: SocketAddr = "192.168.0.30:50000".parse().unwrap();
+    let broadcast: SocketAddr = "192.168.0.30:50001".parse().unwrap();
+    let ploc = pinf::PLoc {
+        listening_tcp_port: 6436,
+        kind: CString::new("LightingConsole").unwrap(),
+        name: CString::new("Console").unwrap(),
+        state: CString::new("Idle").unwrap(),
+    };
+    let pnam = pinf::PNam { name: CString::new("Console").unwrap() };
+
+    // PLoc and PNam sent from different sockets of the same host describe a single peer.
+    let mut peers = Peers::default();
+    assert!(peers.update_ploc(multicast, ploc.clone(), now));
+    assert!(!peers.update_pnam(broadcast, pnam.clone(), now));
+    assert_eq!(peers.len(), 1);
+    let peer = peers.get(multicast.ip(), 6436).unwrap();
+    assert!(!peer.legacy);
+    assert_eq!(peer.addr, multicast);
+
+    // The same applies when the legacy PNam is heard first.
+    let mut peers = Peers::default();
+    assert!(peers.update_pnam(broadcast, pnam.clone(), now));
+    assert!(!peers.update_ploc(multicast, ploc.clone(), now));
+    assert_eq!(peers.len(), 1);
+    assert!(!peers.get(multicast.ip(), 6436).unwrap().legacy);
+
+    // A peer that restarts on a new port replaces its previous entry.
+    let restarted: SocketAddr = "192.168.0.30:50002".parse().unwrap();
+    assert!(!peers.update_ploc(restarted, ploc.clone(), now));
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers.get(restarted.ip(), 6436).unwrap().addr, restarted);
+
+    // Peers on the same host listening on different TCP ports are told apart.
+    let other: SocketAddr = "192.168.0.30:50003".parse().unwrap();
+    let other_ploc = pinf::PLoc {
+        listening_tcp_port: 6437,
+        name: CString::new("Backup Console").unwrap(),
+        ..ploc.clone()
+    };
+    assert!(peers.update_ploc(other, other_ploc.clone(), now));
+    assert!(!peers.update_ploc(restarted, ploc, now));
+    assert!(!peers.update_ploc(other, other_ploc, now));
+    assert_eq!(peers.len(), 2);
+    assert_eq!(peers.get(restarted.ip(), 6436).unwrap().name.to_str().unwrap(), "Console");
+    assert_eq!(peers.get(other.ip(), 6437).unwrap().name.to_str().unwrap(), "Backup Console");
+
+    // A PNam from one of their sockets is attributed to that peer, otherwise it is ignored.
+    let renamed = pinf::PNam { name: CString::new("Backup").unwrap() };
+    assert!(!peers.update_pnam(other, renamed, now));
+    assert!(!peers.update_pnam(broadcast, pnam, now));
+    assert_eq!(peers.len(), 2);
+    assert_eq!(peers.get(other.ip(), 6437).unwrap().name.to_str().unwrap(), "Backup");
+    assert_eq!(peers.get(restarted.ip(), 6436).unwrap().name.to_str().unwrap(), "Console");
+}
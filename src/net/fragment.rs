@@ -0,0 +1,286 @@
+//! ## Multi-part Messages.
+//!
+//! Messages that are too large for a single datagram or packet may be split into multiple parts.
+//! Each part is a complete CITP packet whose CITP header is a copy of the original with the
+//! `message_part_count`, `message_part` and `message_size` fields describing the part. The bytes
+//! following the CITP header of the original message are divided between the parts in order.
+//!
+//! The **fragment** function splits an encoded message into parts and the **Reassembler** buffers
+//! received parts until all parts of a message have arrived.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::net::HEADER_SIZE;
+use crate::protocol::{self, Cookie, Limits, ReadBytes, WriteBytes};
+
+/// The default duration after which an incomplete message is discarded.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Buffers the parts of multi-part messages until all parts have been received.
+///
+/// Parts are keyed by sender, request index and content type, so the parts of messages from
+/// different senders or of different requests may be interleaved. Parts may arrive in any order.
+#[derive(Clone, Debug)]
+pub struct Reassembler<K>
+    where
+        K: Eq + Hash,
+{
+    timeout: Duration,
+    pending: HashMap<Key<K>, Pending>,
+}
+
+/// Identifies the message to which a part belongs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key<K> {
+    sender: K,
+    /// The `kind` as written to bytes, as the layer that determines its variant is not known
+    /// until the message has been reassembled.
+    request_index: u16,
+    content_type: Cookie,
+}
+
+/// The parts of a message received so far.
+#[derive(Clone, Debug)]
+struct Pending {
+    citp_header: protocol::Header,
+    part_count: usize,
+    /// The bytes following the CITP header of each part, keyed by part index.
+    parts: BTreeMap<usize, Vec<u8>>,
+    /// The total number of bytes within `parts`.
+    size: usize,
+    last_received: Instant,
+}
+
+impl<K> Reassembler<K>
+    where
+        K: Eq + Hash,
+{
+    /// Create a new reassembler that discards incomplete messages after the `DEFAULT_TIMEOUT`.
+    pub fn new() -> Self {
+        Self::with_timeout(DEFAULT_TIMEOUT)
+    }
+
+    /// Create a new reassembler that discards incomplete messages that have not received a part
+    /// within the given timeout.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Reassembler {
+            timeout,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// The duration after which an incomplete message is discarded.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Insert a packet received from the given sender.
+    ///
+    /// Returns the complete message once all of its parts have been received. Packets that are not
+    /// part of a multi-part message are returned immediately. The `message_size` of a reassembled
+    /// message describes the entire message and its `message_part_count` is `1`.
+    ///
+    /// Incomplete messages that have timed out are discarded first. Returns an error if the
+    /// reassembled message would exceed the `max_message_size` of the current **Limits**, in which
+    /// case the parts received so far are discarded.
+    pub fn insert(
+        &mut self,
+        sender: K,
+        packet: Vec<u8>,
+        now: Instant,
+    ) -> io::Result<Option<Vec<u8>>> {
+        self.remove_expired(now);
+        let citp_header = read_part_header(&packet)?;
+        let part_count = citp_header.message_part_count as usize;
+        let part = citp_header.message_part as usize;
+        if part_count <= 1 {
+            return Ok(Some(packet));
+        }
+        if part >= part_count {
+            let err_msg = "the message part is out of range of the message part count";
+            return Err(Error::InvalidMessage(err_msg).into());
+        }
+
+        let key = Key {
+            sender,
+            request_index: citp_header.kind.to_u16(),
+            content_type: citp_header.content_type,
+        };
+        let mut entry = match self.pending.entry(key) {
+            Entry::Occupied(entry) => entry,
+            Entry::Vacant(entry) => entry.insert_entry(Pending::new(citp_header, now)),
+        };
+        // A different part count indicates a new message, e.g. after an earlier one was lost.
+        if entry.get().part_count != part_count {
+            entry.insert(Pending::new(citp_header, now));
+        }
+        let pending = entry.get_mut();
+        pending.last_received = now;
+        if !pending.parts.contains_key(&part) {
+            let payload = packet[HEADER_SIZE..].to_vec();
+            pending.size += payload.len();
+            pending.parts.insert(part, payload);
+        }
+        let length = HEADER_SIZE + pending.size;
+        let max = Limits::current().max_message_size;
+        if length > max {
+            entry.remove();
+            return Err(Error::LimitExceeded { limit: "message size", length, max }.into());
+        }
+        if pending.parts.len() < part_count {
+            return Ok(None);
+        }
+        entry.remove().into_message().map(Some)
+    }
+
+    /// Discard all incomplete messages that have not received a part within the timeout.
+    ///
+    /// Returns the number of messages that were discarded.
+    pub fn remove_expired(&mut self, now: Instant) -> usize {
+        let timeout = self.timeout;
+        let len = self.pending.len();
+        self.pending
+            .retain(|_, pending| now.saturating_duration_since(pending.last_received) < timeout);
+        len - self.pending.len()
+    }
+
+    /// The number of incomplete messages.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether or not there are no incomplete messages.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl<K> Default for Reassembler<K>
+    where
+        K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pending {
+    fn new(citp_header: protocol::Header, now: Instant) -> Self {
+        Pending {
+            citp_header,
+            part_count: citp_header.message_part_count as usize,
+            parts: BTreeMap::new(),
+            size: 0,
+            last_received: now,
+        }
+    }
+
+    /// Concatenate the parts into a single message.
+    fn into_message(self) -> io::Result<Vec<u8>> {
+        let mut citp_header = self.citp_header;
+        citp_header.message_size = (HEADER_SIZE + self.size) as u32;
+        citp_header.message_part_count = 1;
+        citp_header.message_part = 0;
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.size);
+        bytes.write_bytes(citp_header)?;
+        for part in self.parts.values() {
+            bytes.extend_from_slice(part);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Split the given encoded message into parts of at most `max_packet_size` bytes each, including
+/// the CITP header at the start of each part.
+///
+/// A message that already fits within `max_packet_size` is returned as a single, unchanged part.
+pub fn fragment(message: &[u8], max_packet_size: usize) -> io::Result<Vec<Vec<u8>>> {
+    let citp_header = read_part_header(message)?;
+    if message.len() <= max_packet_size {
+        return Ok(vec![message.to_vec()]);
+    }
+    if max_packet_size <= HEADER_SIZE {
+        let err_msg = "the maximum packet size must be larger than the CITP header";
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, err_msg));
+    }
+    let payload = &message[HEADER_SIZE..];
+    let chunk_size = max_packet_size - HEADER_SIZE;
+    let part_count = payload.len().div_ceil(chunk_size);
+    if part_count > u16::MAX as usize {
+        let max = u16::MAX as usize;
+        return Err(Error::CountOverflow { count: part_count, max }.into());
+    }
+    payload
+        .chunks(chunk_size)
+        .enumerate()
+        .map(|(part, chunk)| {
+            let mut part_header = citp_header;
+            part_header.message_size = (HEADER_SIZE + chunk.len()) as u32;
+            part_header.message_part_count = part_count as u16;
+            part_header.message_part = part as u16;
+            let mut bytes = Vec::with_capacity(HEADER_SIZE + chunk.len());
+            bytes.write_bytes(part_header)?;
+            bytes.extend_from_slice(chunk);
+            Ok(bytes)
+        })
+        .collect()
+}
+
+/// Read the CITP header of a packet, checking its cookie and that its `message_size` matches the
+/// length of the packet.
+fn read_part_header(packet: &[u8]) -> io::Result<protocol::Header> {
+    let citp_header: protocol::Header = (&packet[..]).read_bytes()?;
+    protocol::check_cookie(citp_header.cookie, protocol::Header::COOKIE)?;
+    if citp_header.message_size as usize != packet.len() {
+        let message_size = citp_header.message_size;
+        return Err(Error::LengthMismatch { message_size, length: packet.len() }.into());
+    }
+    Ok(citp_header)
+}
+
+#[test]
+fn test_fragment_reassemble_out_of_order() {
+    use std::net::SocketAddr;
+    use crate::protocol::msex;
+
+    let vsrc = msex::VSrc {
+        sources: (0..40)
+            .map(|i| msex::SourceInformation {
+                source_identifier: i,
+                source_name: protocol::Ucs2String::new(format!("Source {}", i)).unwrap(),
+                physical_output: 0xFF,
+                layer_number: 0xFF,
+                flags: 0,
+                width: 1920,
+                height: 1080,
+            })
+            .collect(),
+    };
+    let mut message = vec![];
+    message.write_bytes(msex::Message::new(msex::Version::V1_2, vsrc)).unwrap();
+    let mut parts = fragment(&message, 512).unwrap();
+    assert!(parts.len() > 2);
+    assert!(parts.iter().all(|part| part.len() <= 512));
+
+    let a: SocketAddr = "10.0.0.1:6436".parse().unwrap();
+    let b: SocketAddr = "10.0.0.2:6436".parse().unwrap();
+    let now = Instant::now();
+    let mut reassembler = Reassembler::new();
+    let last = parts.remove(0);
+    parts.reverse();
+    for part in &parts {
+        assert_eq!(reassembler.insert(a, part.clone(), now).unwrap(), None);
+        // Parts from a different sender are buffered separately.
+        assert_eq!(reassembler.insert(b, part.clone(), now).unwrap(), None);
+    }
+    assert_eq!(reassembler.len(), 2);
+    assert_eq!(reassembler.insert(a, last, now).unwrap(), Some(message.clone()));
+    assert_eq!(reassembler.len(), 1);
+    assert_eq!(reassembler.remove_expired(now + DEFAULT_TIMEOUT), 1);
+    assert!(reassembler.is_empty());
+}
@@ -0,0 +1,63 @@
+//! ## Networking.
+//!
+//! Implementations of the broadcasting, multicasting, UDP and TCP streams described within the
+//! protocol for communication of the protocol over a network.
+//!
+//! - The **discovery** module provides a service for locating peers via multicast PINF/PLoc (and
+//!   optionally legacy broadcast PINF/PNam) messages and announcing our own location in turn.
+//! - The **connection** module provides a TCP connection with a peer that reads and writes whole
+//!   CITP packets.
+//! - The **fragment** module splits large messages into multiple parts and reassembles received
+//!   parts into complete messages.
+//! - The **tokio** module provides async equivalents of the above when the `tokio` feature is
+//!   enabled.
+
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+
+use crate::protocol::{self, ContentType, SizeBytes, WriteBytes, WriteToBytes, pinf};
+
+pub mod connection;
+pub mod discovery;
+pub mod fragment;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+pub use self::connection::{Connection, Frame, LayerHeader};
+pub use self::discovery::{Config, Discovery, Peer, Peers};
+pub use self::fragment::{Reassembler, fragment};
+
+/// The size of the CITP header at the start of every packet.
+pub(crate) const HEADER_SIZE: usize = 20;
+
+/// Produce a complete PINF packet for the given message.
+pub(crate) fn pinf_packet<T>(message: T) -> io::Result<Vec<u8>>
+    where
+        T: ContentType + SizeBytes + WriteToBytes,
+{
+    let msg = pinf::Message::new(message);
+    let mut bytes = Vec::with_capacity(msg.size_bytes());
+    bytes.write_bytes(&msg)?;
+    Ok(bytes)
+}
+
+/// Whether or not the given header describes a PINF message.
+pub(crate) fn is_pinf(header: &pinf::Header) -> bool {
+    header.citp_header.cookie == protocol::Header::COOKIE
+        && header.citp_header.content_type == pinf::Header::CONTENT_TYPE
+}
+
+/// Create a non-blocking UDP socket bound to the given port on all interfaces.
+///
+/// Address (and where available, port) reuse is enabled so that multiple CITP peers running on the
+/// same host may listen on the same well-known port.
+pub(crate) fn bind_reusable_udp(port: u16) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))))]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port).into())?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into())
+}
@@ -0,0 +1,92 @@
+//! ## CITP Codec.
+//!
+//! A **Codec** that may be used with `tokio_util::codec::Framed` to read **Frame**s from and write
+//! messages to any async byte stream.
+
+use bytes::BytesMut;
+use std::io;
+use std::time::Instant;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::net::connection::{self, Frame};
+use crate::net::{self, Reassembler};
+use crate::protocol::WriteToBytes;
+
+/// Decodes complete CITP packets into **Frame**s and encodes messages with their `message_size`
+/// filled in.
+///
+/// The parts of multi-part messages are buffered until all parts have been received and are
+/// decoded as a single, reassembled **Frame**.
+#[derive(Debug, Default)]
+pub struct Codec {
+    reassembler: Reassembler<()>,
+}
+
+impl Codec {
+    /// Create a new codec.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Decoder for Codec {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Frame>> {
+        loop {
+            if src.len() < net::HEADER_SIZE {
+                return Ok(None);
+            }
+            let message_size = connection::read_message_size(&src[..])?;
+            if src.len() < message_size {
+                src.reserve(message_size - src.len());
+                return Ok(None);
+            }
+            let bytes = src.split_to(message_size).to_vec();
+            if let Some(bytes) = self.reassembler.insert((), bytes, Instant::now())? {
+                return Frame::from_bytes(bytes).map(Some);
+            }
+        }
+    }
+}
+
+impl<T> Encoder<T> for Codec
+    where
+        T: WriteToBytes,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, message: T, dst: &mut BytesMut) -> io::Result<()> {
+        let bytes = connection::encode_message(&message)?;
+        dst.extend_from_slice(&bytes);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_codec_partial_and_fragmented() {
+    use std::ffi::CString;
+    use crate::protocol::pinf;
+
+    let message = pinf::Message::new(pinf::PNam { name: CString::new("Visualiser").unwrap() });
+    let mut codec = Codec::new();
+    let mut encoded = BytesMut::new();
+    codec.encode(&message, &mut encoded).unwrap();
+    let bytes = encoded.to_vec();
+
+    // A partially received packet is not decoded until the remainder arrives.
+    let mut src = BytesMut::from(&bytes[..10]);
+    assert!(codec.decode(&mut src).unwrap().is_none());
+    src.extend_from_slice(&bytes[10..]);
+    let frame = codec.decode(&mut src).unwrap().unwrap();
+    assert_eq!(frame.bytes, bytes);
+    assert!(src.is_empty());
+
+    // All parts of a multi-part message are decoded as a single frame.
+    let mut src = BytesMut::new();
+    for part in net::fragment(&bytes, 24).unwrap() {
+        src.extend_from_slice(&part);
+    }
+    assert_eq!(codec.decode(&mut src).unwrap().unwrap(), frame);
+}
@@ -0,0 +1,142 @@
+//! ## Async Peer Connections.
+//!
+//! An async equivalent of **net::Connection** that reads one complete CITP packet at a time
+//! without blocking the runtime.
+
+use bytes::BytesMut;
+use std::ffi::CString;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio_util::codec::Decoder;
+
+use crate::net::connection::{self, Frame};
+use crate::net::tokio::Codec;
+use crate::protocol::{WriteToBytes, pinf};
+
+/// The initial capacity of the read buffer.
+const READ_BUFFER_CAPACITY: usize = 4_096;
+
+/// An async TCP connection with a CITP peer.
+#[derive(Debug)]
+pub struct Connection {
+    stream: TcpStream,
+    codec: Codec,
+    buffer: BytesMut,
+}
+
+impl Connection {
+    /// Connect to the peer at the given address, sending a PINF/PNam message with the given name
+    /// as the first message as required by the PINF layer.
+    pub async fn connect<A>(addr: A, name: CString) -> io::Result<Self>
+        where
+            A: ToSocketAddrs,
+    {
+        let stream = TcpStream::connect(addr).await?;
+        let mut connection = Connection::from_stream(stream);
+        connection.write_message(&pinf::Message::new(pinf::PNam { name })).await?;
+        Ok(connection)
+    }
+
+    /// Wrap a stream that is already connected, e.g. one accepted by a **TcpListener**.
+    ///
+    /// No messages are sent.
+    pub fn from_stream(stream: TcpStream) -> Self {
+        Connection {
+            stream,
+            codec: Codec::new(),
+            buffer: BytesMut::with_capacity(READ_BUFFER_CAPACITY),
+        }
+    }
+
+    /// Read the next complete CITP packet, waiting until it has been received in full.
+    ///
+    /// The parts of multi-part messages are buffered until all parts have been received and are
+    /// returned as a single, reassembled packet.
+    ///
+    /// Returns an error of kind `UnexpectedEof` if the peer closes the connection.
+    pub async fn read_frame(&mut self) -> io::Result<Frame> {
+        loop {
+            if let Some(frame) = self.codec.decode(&mut self.buffer)? {
+                return Ok(frame);
+            }
+            if self.stream.read_buf(&mut self.buffer).await? == 0 {
+                let err_msg = "the connection was closed by the peer";
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, err_msg));
+            }
+        }
+    }
+
+    /// Write the given message, filling in the `message_size` of its CITP header.
+    pub async fn write_message<T>(&mut self, message: &T) -> io::Result<()>
+        where
+            T: WriteToBytes,
+    {
+        let bytes = connection::encode_message(message)?;
+        self.stream.write_all(&bytes).await?;
+        self.stream.flush().await
+    }
+
+    /// The address of the remote peer.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// The local address of the connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.local_addr()
+    }
+
+    /// Shut down the writing half of the connection.
+    pub async fn shutdown(&mut self) -> io::Result<()> {
+        self.stream.shutdown().await
+    }
+
+    /// A reference to the inner **TcpStream**, e.g. for configuring socket options.
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Consume the connection, returning the inner **TcpStream**.
+    ///
+    /// Any bytes that have been received but not yet returned as a frame are discarded.
+    pub fn into_stream(self) -> TcpStream {
+        self.stream
+    }
+}
+
+#[test]
+fn test_connection_pnam_first_and_framing() {
+    use crate::protocol;
+    use tokio::net::TcpListener;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let name = CString::new("Console").unwrap();
+            let mut connection = Connection::connect(addr, name).await.unwrap();
+            let ploc = pinf::PLoc {
+                listening_tcp_port: 0,
+                kind: CString::new("LightingConsole").unwrap(),
+                name: CString::new("Console").unwrap(),
+                state: CString::new("Idle").unwrap(),
+            };
+            connection.write_message(&pinf::Message::new(ploc)).await.unwrap();
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut connection = Connection::from_stream(stream);
+        let pnam = connection.read_frame().await.unwrap();
+        assert_eq!(pnam.layer_content_type().unwrap(), pinf::PNam::CONTENT_TYPE);
+        match connection.read_frame().await.unwrap().packet().unwrap() {
+            protocol::Packet::PLoc(msg) => assert_eq!(msg.message.state.to_str().unwrap(), "Idle"),
+            packet => panic!("expected a PLoc packet, found {:?}", packet),
+        }
+        client.await.unwrap();
+        let err = connection.read_frame().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    });
+}
@@ -0,0 +1,176 @@
+//! ## Async Peer Discovery.
+//!
+//! An async equivalent of **net::Discovery**. Rather than being polled regularly, the
+//! **Discovery::update** method waits until an announcement is received or our own **PLoc** is
+//! due to be announced.
+
+use std::future;
+use std::io;
+use std::time::Instant;
+use tokio::net::UdpSocket;
+
+use crate::net::discovery::{self, Config, Peer, Peers, Sockets, State};
+use crate::protocol::pinf;
+
+/// A service that announces our own location and discovers the location of other peers.
+#[derive(Debug)]
+pub struct Discovery {
+    socket: UdpSocket,
+    legacy_socket: Option<UdpSocket>,
+    sender: UdpSocket,
+    state: State,
+}
+
+impl Discovery {
+    /// Begin discovering peers using the default **Config**, announcing ourselves with the given
+    /// **PLoc**.
+    pub fn new(ploc: pinf::PLoc) -> io::Result<Self> {
+        Self::with_config(ploc, Config::default())
+    }
+
+    /// Begin discovering peers using the given **Config**, announcing ourselves with the given
+    /// **PLoc**.
+    pub fn with_config(ploc: pinf::PLoc, config: Config) -> io::Result<Self> {
+        let Sockets { socket, legacy_socket, sender } = discovery::bind_sockets(&config)?;
+        sender.set_nonblocking(true)?;
+        let state = State::new(ploc, config, sender.local_addr()?.port());
+        Ok(Discovery {
+            socket: UdpSocket::from_std(socket)?,
+            legacy_socket: legacy_socket.map(UdpSocket::from_std).transpose()?,
+            sender: UdpSocket::from_std(sender)?,
+            state,
+        })
+    }
+
+    /// The configuration with which the service was created.
+    pub fn config(&self) -> &Config {
+        self.state.config()
+    }
+
+    /// The **PLoc** with which we announce ourselves.
+    pub fn ploc(&self) -> &pinf::PLoc {
+        self.state.ploc()
+    }
+
+    /// Change the **PLoc** with which we announce ourselves, e.g. to update our display state.
+    ///
+    /// The new **PLoc** is announced upon the next call to **Discovery::update**.
+    pub fn set_ploc(&mut self, ploc: pinf::PLoc) {
+        self.state.set_ploc(ploc);
+    }
+
+    /// The table of currently known peers.
+    pub fn peers(&self) -> &Peers {
+        self.state.peers()
+    }
+
+    /// Immediately multicast our **PLoc**.
+    ///
+    /// If **Config::legacy_broadcast** is enabled, a **PNam** with our name is also broadcast.
+    pub async fn announce(&mut self) -> io::Result<()> {
+        for (datagram, addr) in self.state.announcements()? {
+            self.sender.send_to(datagram, addr).await?;
+        }
+        self.state.announced(Instant::now());
+        Ok(())
+    }
+
+    /// Wait until an announcement is received or our **PLoc** is due to be announced, then
+    /// process all pending messages, announce our **PLoc** if the announce interval has elapsed
+    /// and remove all peers that have not been heard from within the peer timeout.
+    ///
+    /// Returns the peers that were removed.
+    pub async fn update(&mut self) -> io::Result<Vec<Peer>> {
+        let next_announcement = self.state.next_announcement(Instant::now());
+        let legacy_socket = self.legacy_socket.as_ref();
+        let legacy_readable = async {
+            match legacy_socket {
+                Some(socket) => socket.readable().await,
+                None => future::pending().await,
+            }
+        };
+        tokio::select! {
+            result = self.socket.readable() => result?,
+            result = legacy_readable => result?,
+            _ = tokio::time::sleep_until(next_announcement.into()) => (),
+        }
+        self.receive()?;
+        let now = Instant::now();
+        if now >= next_announcement {
+            self.announce().await?;
+        }
+        Ok(self.state.remove_expired(now))
+    }
+
+    /// Read all datagrams that are pending on the sockets without waiting.
+    fn receive(&mut self) -> io::Result<()> {
+        let Discovery { ref socket, ref legacy_socket, ref mut state, .. } = *self;
+        for socket in Some(socket).into_iter().chain(legacy_socket) {
+            state.receive_pending(|buffer| socket.try_recv_from(buffer))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_discovery_update_wakes() {
+    use crate::net;
+    use std::ffi::CString;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let ploc = pinf::PLoc {
+            listening_tcp_port: 0,
+            kind: CString::new("Visualiser").unwrap(),
+            name: CString::new("Visualiser").unwrap(),
+            state: CString::new("Idle").unwrap(),
+        };
+        let config = Config {
+            announce_interval: Duration::from_secs(60),
+            interface: Ipv4Addr::LOCALHOST,
+            legacy_broadcast: true,
+            ..Config::default()
+        };
+        let mut discovery = Discovery::with_config(ploc.clone(), config).unwrap();
+        let wait = Duration::from_secs(5);
+
+        // The first update announces immediately.
+        timeout(wait, discovery.update()).await.unwrap().unwrap();
+
+        // A PLoc received on the multicast port wakes the update. Our own looped back announcements
+        // are ignored.
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_ploc = pinf::PLoc {
+            listening_tcp_port: 6436,
+            kind: CString::new("MediaServer").unwrap(),
+            name: CString::new("Server").unwrap(),
+            state: CString::new("Running").unwrap(),
+        };
+        let packet = net::pinf_packet(&server_ploc).unwrap();
+        server.send_to(&packet, (Ipv4Addr::LOCALHOST, pinf::MULTICAST_PORT)).unwrap();
+        timeout(wait, discovery.update()).await.unwrap().unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let peer = discovery.peers().get(server_addr.ip(), 6436).unwrap();
+        assert_eq!(peer.addr, server_addr);
+        assert_eq!(discovery.peers().len(), 1);
+
+        // A PNam received on the legacy broadcast port wakes the update.
+        let legacy = std::net::UdpSocket::bind("127.0.0.2:0").unwrap();
+        let pnam = pinf::PNam { name: CString::new("Legacy").unwrap() };
+        let packet = net::pinf_packet(&pnam).unwrap();
+        legacy.send_to(&packet, (Ipv4Addr::LOCALHOST, pinf::OLD_BROADCAST_PORT)).unwrap();
+        timeout(wait, discovery.update()).await.unwrap().unwrap();
+        let legacy_addr = legacy.local_addr().unwrap();
+        assert!(discovery.peers().get(legacy_addr.ip(), 0).unwrap().legacy);
+        assert_eq!(discovery.peers().len(), 2);
+
+        // Changing our PLoc makes it due, waking the update without any received messages.
+        let ploc = pinf::PLoc { state: CString::new("Running").unwrap(), ..ploc };
+        discovery.set_ploc(ploc);
+        timeout(wait, discovery.update()).await.unwrap().unwrap();
+        assert_eq!(discovery.ploc().state.to_str().unwrap(), "Running");
+    });
+}
@@ -0,0 +1,20 @@
+//! ## Async Networking.
+//!
+//! Async equivalents of the **net** module types for use with the tokio runtime. Enabled via the
+//! `tokio` cargo feature.
+//!
+//! - The **discovery** module provides a **Discovery** service whose **update** method awaits the
+//!   next received announcement or due announcement of our own **PLoc**.
+//! - The **connection** module provides a TCP **Connection** with a peer.
+//! - The **codec** module provides a `tokio_util::codec` **Codec** for reading and writing whole
+//!   CITP packets, e.g. via `tokio_util::codec::Framed`.
+//!
+//! All types must be created within the context of a tokio runtime.
+
+pub mod codec;
+pub mod connection;
+pub mod discovery;
+
+pub use self::codec::Codec;
+pub use self::connection::Connection;
+pub use self::discovery::Discovery;
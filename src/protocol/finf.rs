@@ -2,23 +2,25 @@ use std::{io, mem};
 use std::borrow::Cow;
 use std::ffi::CString;
 
-use protocol::{
-    self, LE, ReadBytes, ReadBytesExt, ReadFromBytes, SizeBytes, WriteBytes,
-    WriteBytesExt, WriteToBytes,
+use crate::protocol::{
+    self, ContentType, Cookie, LE, ReadBytes, ReadBytesExt, ReadFromBytes, ReadFromSlice, ReadSlice,
+    SizeBytes, WriteBytes, WriteBytesExt, WriteToBytes, limits,
 };
 
 /// The FINF layer provides a standard, single, header used at the start of all FINF packets.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Header {
     /// The CITP header. CITP ContentType is "FINF".
     pub citp_header: protocol::Header,
     /// A cookie defining which FINF message it is.
-    pub content_type: u32,
+    pub content_type: Cookie,
 }
 
 /// Layout of FINF messages.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Message<T> {
     /// The FINF header - the base header with the FINF content type.
@@ -31,6 +33,7 @@ pub struct Message<T> {
 ///
 /// This message informs the receiver to send frame messages for the specified fixtures.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SFra<'a> {
     /// List of fixture identifiers.
     pub fixture_identifiers: Cow<'a, [u16]>,
@@ -40,6 +43,7 @@ pub struct SFra<'a> {
 ///
 /// This message informs the receiver about the filters & gobos of a fixture.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fram {
     /// The fixture identifier.
     pub fixture_identifier: u16,
@@ -50,6 +54,7 @@ pub struct Fram {
     /// List of (first) filters and (last) gobos, newline separated (\n) & null terminated.
     ///
     /// Always contains *at least* the null.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub frame_names: CString,
 }
 
@@ -65,10 +70,39 @@ impl Fram {
     pub const CONTENT_TYPE: &'static [u8; 4] = b"Fram";
 }
 
+impl<'a> ContentType for SFra<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = SFra::CONTENT_TYPE;
+}
+
+impl ContentType for Fram {
+    const CONTENT_TYPE: &'static [u8; 4] = Fram::CONTENT_TYPE;
+}
+
+impl<T> Message<T>
+    where
+        T: ContentType + SizeBytes,
+{
+    /// Create a new FINF message, filling in the cookies of all headers and the `message_size` of
+    /// the CITP header.
+    pub fn new(message: T) -> Self {
+        let citp_content_type = Cookie::new(Header::CONTENT_TYPE);
+        let finf_header = Header {
+            citp_header: protocol::Header::new(citp_content_type, Default::default(), 0),
+            content_type: Cookie::new(T::CONTENT_TYPE),
+        };
+        let mut msg = Message {
+            finf_header,
+            message,
+        };
+        msg.finf_header.citp_header.message_size = msg.size_bytes() as u32;
+        msg
+    }
+}
+
 impl WriteToBytes for Header {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.citp_header)?;
-        writer.write_u32::<LE>(self.content_type)?;
+        writer.write_bytes(self.citp_header)?;
+        writer.write_bytes(self.content_type)?;
         Ok(())
     }
 }
@@ -78,7 +112,7 @@ impl<T> WriteToBytes for Message<T>
         T: WriteToBytes,
 {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.finf_header)?;
+        writer.write_bytes(self.finf_header)?;
         writer.write_bytes(&self.message)?;
         Ok(())
     }
@@ -86,7 +120,7 @@ impl<T> WriteToBytes for Message<T>
 
 impl<'a> WriteToBytes for SFra<'a> {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u16::<LE>(self.fixture_identifiers.len() as _)?;
+        protocol::write_count_u16(&mut writer, self.fixture_identifiers.len())?;
         for &id in self.fixture_identifiers.iter() {
             writer.write_u16::<LE>(id)?;
         }
@@ -104,6 +138,40 @@ impl WriteToBytes for Fram {
     }
 }
 
+impl ReadFromBytes for Header {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let citp_header = reader.read_bytes()?;
+        let content_type = reader.read_bytes()?;
+        let header = Header {
+            citp_header,
+            content_type,
+        };
+        Ok(header)
+    }
+}
+
+impl<T> ReadFromBytes for Message<T>
+    where
+        T: ContentType + ReadFromBytes,
+{
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let finf_header: Header = reader.read_bytes()?;
+        protocol::check_cookie(finf_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(finf_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(finf_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = finf_header.citp_header.message_size;
+        let headers_size = finf_header.size_bytes();
+        let message = limits::read_message_body(reader, message_size, headers_size, |r| {
+            r.read_bytes()
+        })?;
+        let msg = Message {
+            finf_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
 impl ReadFromBytes for SFra<'static> {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
         let fixture_count = reader.read_u16::<LE>()?;
@@ -132,6 +200,54 @@ impl ReadFromBytes for Fram {
     }
 }
 
+impl<'a, T> ReadFromSlice<'a> for Message<T>
+    where
+        T: ContentType + ReadFromSlice<'a>,
+{
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let finf_header: Header = bytes.read_bytes()?;
+        protocol::check_cookie(finf_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(finf_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(finf_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = finf_header.citp_header.message_size;
+        let headers_size = finf_header.size_bytes();
+        let message = limits::read_message_body_slice(bytes, message_size, headers_size, |b| {
+            b.read_slice()
+        })?;
+        let msg = Message {
+            finf_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
+impl<'a> ReadFromSlice<'a> for SFra<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let fixture_count: u16 = bytes.read_bytes()?;
+        let fixture_identifiers = protocol::read_cow_u16s(bytes, fixture_count as _)?;
+        let sfra = SFra {
+            fixture_identifiers,
+        };
+        Ok(sfra)
+    }
+}
+
+impl SizeBytes for Header {
+    fn size_bytes(&self) -> usize {
+        self.citp_header.size_bytes() + mem::size_of::<u32>()
+    }
+}
+
+impl<T> SizeBytes for Message<T>
+    where
+        T: SizeBytes,
+{
+    fn size_bytes(&self) -> usize {
+        self.finf_header.size_bytes() + self.message.size_bytes()
+    }
+}
+
 impl<'a> SizeBytes for SFra<'a> {
     fn size_bytes(&self) -> usize {
         mem::size_of::<u16>() + self.fixture_identifiers.len() * mem::size_of::<u16>()
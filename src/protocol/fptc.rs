@@ -2,19 +2,20 @@ use std::{io, mem};
 use std::borrow::Cow;
 use std::ffi::CString;
 
-use protocol::{
-    self, LE, ReadBytes, ReadBytesExt, ReadFromBytes, SizeBytes, WriteBytes,
-    WriteBytesExt, WriteToBytes,
+use crate::protocol::{
+    self, ContentType, Cookie, LE, ReadBytes, ReadBytesExt, ReadFromBytes, ReadFromSlice, ReadSlice,
+    SizeBytes, WriteBytes, WriteBytesExt, WriteToBytes, limits,
 };
 
 /// The FPTC layer provides a standard, single, header used at the start of all FPTC packets.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Header {
     /// The CITP header. CITP ContentType is "FPTC".
     pub citp_header: protocol::Header,
     /// A cookie defining which FPTC message it is.
-    pub content_type: u32,
+    pub content_type: Cookie,
     /// Content hint flags:
     /// - 0x00000001 - Message part of a sequence of messages.
     /// - 0x00000002 - Message part of and ends a sequence of messages.
@@ -23,6 +24,7 @@ pub struct Header {
 
 /// Layout of FPTC messages.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Message<T> {
     /// The FPTC header - the base header with the FPTC content type.
@@ -37,6 +39,7 @@ pub struct Message<T> {
 /// the identifier of the fixture added, the sender fixture (library) type make and name of the
 /// fixture added and the patching information.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Ptch {
     /// Fixture identifier.
@@ -50,8 +53,10 @@ pub struct Ptch {
     /// Patch channel count (`1`-`512`).
     pub channel_count: u16,
     /// Fixture make (only `null` if omitted).
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub fixture_make: CString,
     /// Fixture name (never omitted).
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub fixture_name: CString,
 }
 
@@ -61,6 +66,7 @@ pub struct Ptch {
 /// contains the identifiers of the fixtures removed. An empty fixture identifier array indicates
 /// complete unpatching.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct UPtc<'a> {
     /// Specific fixtures to unpatch.
@@ -74,6 +80,7 @@ pub struct UPtc<'a> {
 /// the entire **Patch** should be transferred in response. This procedure can be used for testing
 /// existence of fixtures on the remote side or to synchronise the entire patch information.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct SPtc<'a> {
     /// Specific fixtures to unpatch.
@@ -96,10 +103,44 @@ impl<'a> SPtc<'a> {
     pub const CONTENT_TYPE: &'static [u8; 4] = b"SPtc";
 }
 
+impl ContentType for Ptch {
+    const CONTENT_TYPE: &'static [u8; 4] = Ptch::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for UPtc<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = UPtc::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for SPtc<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = SPtc::CONTENT_TYPE;
+}
+
+impl<T> Message<T>
+    where
+        T: ContentType + SizeBytes,
+{
+    /// Create a new FPTC message, filling in the cookies of all headers and the `message_size` of
+    /// the CITP header.
+    pub fn new(message: T) -> Self {
+        let citp_content_type = Cookie::new(Header::CONTENT_TYPE);
+        let fptc_header = Header {
+            citp_header: protocol::Header::new(citp_content_type, Default::default(), 0),
+            content_type: Cookie::new(T::CONTENT_TYPE),
+            content_hint: 0,
+        };
+        let mut msg = Message {
+            fptc_header,
+            message,
+        };
+        msg.fptc_header.citp_header.message_size = msg.size_bytes() as u32;
+        msg
+    }
+}
+
 impl WriteToBytes for Header {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.citp_header)?;
-        writer.write_u32::<LE>(self.content_type)?;
+        writer.write_bytes(self.citp_header)?;
+        writer.write_bytes(self.content_type)?;
         writer.write_u32::<LE>(self.content_hint)?;
         Ok(())
     }
@@ -110,7 +151,7 @@ impl<T> WriteToBytes for Message<T>
         T: WriteToBytes,
 {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.fptc_header)?;
+        writer.write_bytes(self.fptc_header)?;
         writer.write_bytes(&self.message)?;
         Ok(())
     }
@@ -131,7 +172,7 @@ impl WriteToBytes for Ptch {
 
 impl<'a> WriteToBytes for UPtc<'a> {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u16::<LE>(self.fixture_identifiers.len() as _)?;
+        protocol::write_count_u16(&mut writer, self.fixture_identifiers.len())?;
         for &id in self.fixture_identifiers.iter() {
             writer.write_u16::<LE>(id)?;
         }
@@ -141,7 +182,7 @@ impl<'a> WriteToBytes for UPtc<'a> {
 
 impl<'a> WriteToBytes for SPtc<'a> {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u16::<LE>(self.fixture_identifiers.len() as _)?;
+        protocol::write_count_u16(&mut writer, self.fixture_identifiers.len())?;
         for &id in self.fixture_identifiers.iter() {
             writer.write_u16::<LE>(id)?;
         }
@@ -149,6 +190,42 @@ impl<'a> WriteToBytes for SPtc<'a> {
     }
 }
 
+impl ReadFromBytes for Header {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let citp_header = reader.read_bytes()?;
+        let content_type = reader.read_bytes()?;
+        let content_hint = reader.read_u32::<LE>()?;
+        let header = Header {
+            citp_header,
+            content_type,
+            content_hint,
+        };
+        Ok(header)
+    }
+}
+
+impl<T> ReadFromBytes for Message<T>
+    where
+        T: ContentType + ReadFromBytes,
+{
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let fptc_header: Header = reader.read_bytes()?;
+        protocol::check_cookie(fptc_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(fptc_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(fptc_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = fptc_header.citp_header.message_size;
+        let headers_size = fptc_header.size_bytes();
+        let message = limits::read_message_body(reader, message_size, headers_size, |r| {
+            r.read_bytes()
+        })?;
+        let msg = Message {
+            fptc_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
 impl ReadFromBytes for Ptch {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
         let fixture_identifier = reader.read_u16::<LE>()?;
@@ -195,6 +272,65 @@ impl ReadFromBytes for SPtc<'static> {
     }
 }
 
+impl<'a, T> ReadFromSlice<'a> for Message<T>
+    where
+        T: ContentType + ReadFromSlice<'a>,
+{
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let fptc_header: Header = bytes.read_bytes()?;
+        protocol::check_cookie(fptc_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(fptc_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(fptc_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = fptc_header.citp_header.message_size;
+        let headers_size = fptc_header.size_bytes();
+        let message = limits::read_message_body_slice(bytes, message_size, headers_size, |b| {
+            b.read_slice()
+        })?;
+        let msg = Message {
+            fptc_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
+impl<'a> ReadFromSlice<'a> for UPtc<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let fixture_count: u16 = bytes.read_bytes()?;
+        let fixture_identifiers = protocol::read_cow_u16s(bytes, fixture_count as _)?;
+        let uptc = UPtc {
+            fixture_identifiers,
+        };
+        Ok(uptc)
+    }
+}
+
+impl<'a> ReadFromSlice<'a> for SPtc<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let fixture_count: u16 = bytes.read_bytes()?;
+        let fixture_identifiers = protocol::read_cow_u16s(bytes, fixture_count as _)?;
+        let sptc = SPtc {
+            fixture_identifiers,
+        };
+        Ok(sptc)
+    }
+}
+
+impl SizeBytes for Header {
+    fn size_bytes(&self) -> usize {
+        self.citp_header.size_bytes() + mem::size_of::<u32>() + mem::size_of::<u32>()
+    }
+}
+
+impl<T> SizeBytes for Message<T>
+    where
+        T: SizeBytes,
+{
+    fn size_bytes(&self) -> usize {
+        self.fptc_header.size_bytes() + self.message.size_bytes()
+    }
+}
+
 impl SizeBytes for Ptch {
     fn size_bytes(&self) -> usize {
         mem::size_of::<u16>()
@@ -209,12 +345,24 @@ impl SizeBytes for Ptch {
 
 impl<'a> SizeBytes for UPtc<'a> {
     fn size_bytes(&self) -> usize {
-        self.fixture_identifiers.len() * mem::size_of::<u16>()
+        mem::size_of::<u16>() + self.fixture_identifiers.len() * mem::size_of::<u16>()
     }
 }
 
 impl<'a> SizeBytes for SPtc<'a> {
     fn size_bytes(&self) -> usize {
-        self.fixture_identifiers.len() * mem::size_of::<u16>()
+        mem::size_of::<u16>() + self.fixture_identifiers.len() * mem::size_of::<u16>()
     }
 }
+
+#[test]
+fn test_message_new_message_size() {
+    let uptc = UPtc { fixture_identifiers: Cow::Borrowed(&[1, 2, 3]) };
+    let msg = Message::new(uptc);
+    let mut buffer = vec![];
+    buffer.write_bytes(&msg).unwrap();
+    assert_eq!(msg.fptc_header.citp_header.message_size as usize, buffer.len());
+    assert_eq!(msg.fptc_header.content_type, UPtc::CONTENT_TYPE);
+    let read = buffer.as_slice().read_bytes::<Message<UPtc<'static>>>().unwrap();
+    assert_eq!(read, msg);
+}
@@ -1,22 +1,25 @@
 use std::{io, mem};
 use std::borrow::Cow;
 
-use protocol::{
-    self, LE, ReadBytesExt, ReadFromBytes, SizeBytes, WriteBytes, WriteBytesExt, WriteToBytes,
+use crate::protocol::{
+    self, ContentType, Cookie, LE, ReadBytes, ReadBytesExt, ReadFromBytes, ReadFromSlice, ReadSlice,
+    SizeBytes, WriteBytes, WriteBytesExt, WriteToBytes, limits,
 };
 
 /// The FSEL layer provides a standard, single, header used at the start of all FSEL packets.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Header {
     /// The CITP header. CITP ContentType is "FPTC".
     pub citp_header: protocol::Header,
     /// A cookie defining which FSEL message it is.
-    pub content_type: u32,
+    pub content_type: Cookie,
 }
 
 /// Layout of FSEL messages.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Message<T> {
     /// The FSEL header - the base header with the FSEL content type.
@@ -31,6 +34,7 @@ pub struct Message<T> {
 /// field is non-zero, only the fixtures identified in the message should be selected and all
 /// others should be deselected, thus achieving a full synchronisation.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Sele<'a> {
     /// Set to non-zero for complete selection.
@@ -47,6 +51,7 @@ pub struct Sele<'a> {
 /// deselects the fixture specified, rather than selecting them. A Deselect with no fixture
 /// specified should deselect all fixtures.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct DeSe<'a> {
     /// List of fixture identifiers.
@@ -65,10 +70,39 @@ impl<'a> DeSe<'a> {
     pub const CONTENT_TYPE: &'static [u8; 4] = b"DeSe";
 }
 
+impl<'a> ContentType for Sele<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = Sele::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for DeSe<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = DeSe::CONTENT_TYPE;
+}
+
+impl<T> Message<T>
+    where
+        T: ContentType + SizeBytes,
+{
+    /// Create a new FSEL message, filling in the cookies of all headers and the `message_size` of
+    /// the CITP header.
+    pub fn new(message: T) -> Self {
+        let citp_content_type = Cookie::new(Header::CONTENT_TYPE);
+        let fsel_header = Header {
+            citp_header: protocol::Header::new(citp_content_type, Default::default(), 0),
+            content_type: Cookie::new(T::CONTENT_TYPE),
+        };
+        let mut msg = Message {
+            fsel_header,
+            message,
+        };
+        msg.fsel_header.citp_header.message_size = msg.size_bytes() as u32;
+        msg
+    }
+}
+
 impl WriteToBytes for Header {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.citp_header)?;
-        writer.write_u32::<LE>(self.content_type)?;
+        writer.write_bytes(self.citp_header)?;
+        writer.write_bytes(self.content_type)?;
         Ok(())
     }
 }
@@ -78,7 +112,7 @@ impl<T> WriteToBytes for Message<T>
         T: WriteToBytes,
 {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.fsel_header)?;
+        writer.write_bytes(self.fsel_header)?;
         writer.write_bytes(&self.message)?;
         Ok(())
     }
@@ -88,7 +122,7 @@ impl<'a> WriteToBytes for Sele<'a> {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
         writer.write_u8(self.complete)?;
         writer.write_u8(self.reserved)?;
-        writer.write_u16::<LE>(self.fixture_identifiers.len() as _)?;
+        protocol::write_count_u16(&mut writer, self.fixture_identifiers.len())?;
         for &id in self.fixture_identifiers.iter() {
             writer.write_u16::<LE>(id)?;
         }
@@ -98,7 +132,7 @@ impl<'a> WriteToBytes for Sele<'a> {
 
 impl<'a> WriteToBytes for DeSe<'a> {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u16::<LE>(self.fixture_identifiers.len() as _)?;
+        protocol::write_count_u16(&mut writer, self.fixture_identifiers.len())?;
         for &id in self.fixture_identifiers.iter() {
             writer.write_u16::<LE>(id)?;
         }
@@ -106,6 +140,40 @@ impl<'a> WriteToBytes for DeSe<'a> {
     }
 }
 
+impl ReadFromBytes for Header {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let citp_header = reader.read_bytes()?;
+        let content_type = reader.read_bytes()?;
+        let header = Header {
+            citp_header,
+            content_type,
+        };
+        Ok(header)
+    }
+}
+
+impl<T> ReadFromBytes for Message<T>
+    where
+        T: ContentType + ReadFromBytes,
+{
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let fsel_header: Header = reader.read_bytes()?;
+        protocol::check_cookie(fsel_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(fsel_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(fsel_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = fsel_header.citp_header.message_size;
+        let headers_size = fsel_header.size_bytes();
+        let message = limits::read_message_body(reader, message_size, headers_size, |r| {
+            r.read_bytes()
+        })?;
+        let msg = Message {
+            fsel_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
 impl ReadFromBytes for Sele<'static> {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
         let complete = reader.read_u8()?;
@@ -134,6 +202,69 @@ impl ReadFromBytes for DeSe<'static> {
     }
 }
 
+impl<'a, T> ReadFromSlice<'a> for Message<T>
+    where
+        T: ContentType + ReadFromSlice<'a>,
+{
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let fsel_header: Header = bytes.read_bytes()?;
+        protocol::check_cookie(fsel_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(fsel_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(fsel_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = fsel_header.citp_header.message_size;
+        let headers_size = fsel_header.size_bytes();
+        let message = limits::read_message_body_slice(bytes, message_size, headers_size, |b| {
+            b.read_slice()
+        })?;
+        let msg = Message {
+            fsel_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
+impl<'a> ReadFromSlice<'a> for Sele<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let complete = bytes.read_u8()?;
+        let reserved = bytes.read_u8()?;
+        let fixture_count = bytes.read_u16::<LE>()?;
+        let fixture_identifiers = protocol::read_cow_u16s(bytes, fixture_count as _)?;
+        let sele = Sele {
+            complete,
+            reserved,
+            fixture_identifiers,
+        };
+        Ok(sele)
+    }
+}
+
+impl<'a> ReadFromSlice<'a> for DeSe<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let fixture_count: u16 = bytes.read_bytes()?;
+        let fixture_identifiers = protocol::read_cow_u16s(bytes, fixture_count as _)?;
+        let dese = DeSe {
+            fixture_identifiers,
+        };
+        Ok(dese)
+    }
+}
+
+impl SizeBytes for Header {
+    fn size_bytes(&self) -> usize {
+        self.citp_header.size_bytes() + mem::size_of::<u32>()
+    }
+}
+
+impl<T> SizeBytes for Message<T>
+    where
+        T: SizeBytes,
+{
+    fn size_bytes(&self) -> usize {
+        self.fsel_header.size_bytes() + self.message.size_bytes()
+    }
+}
+
 impl<'a> SizeBytes for Sele<'a> {
     fn size_bytes(&self) -> usize {
         mem::size_of::<u8>()
@@ -0,0 +1,224 @@
+//! ## Decoding Limits.
+//!
+//! Packets received from the network are untrusted. A malformed packet may describe a string with
+//! no null terminator, an enormous element count or a `message_size` far larger than the data that
+//! follows. The **Limits** type describes the maximum sizes accepted while decoding.
+//!
+//! All readers consult the limits of the current decoding context. By default this is
+//! **Limits::default()**. A different set of limits may be applied while reading using
+//! **Limits::read**. While reading the body of a message, the limits are further bounded by the
+//! number of bytes remaining within the message as described by the `message_size` of the CITP
+//! header.
+
+use std::cell::Cell;
+use std::io;
+
+use crate::error::Error;
+use crate::protocol::{ReadBytes, ReadBytesExt, ReadFromBytes};
+
+/// The default maximum length of a string, excluding the null terminator.
+pub const DEFAULT_MAX_STRING_LEN: usize = 4_096;
+
+/// The default maximum number of elements within a list.
+pub const DEFAULT_MAX_LIST_LEN: usize = u16::MAX as usize;
+
+/// The default maximum `message_size` of a CITP packet (16 MiB).
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// The maximum sizes accepted while decoding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// The maximum length of a string in bytes (or units for UCS-2 strings), excluding the null
+    /// terminator.
+    pub max_string_len: usize,
+    /// The maximum number of elements within a list.
+    pub max_list_len: usize,
+    /// The maximum `message_size` of a CITP packet, including all headers.
+    pub max_message_size: usize,
+}
+
+thread_local! {
+    static CURRENT: Cell<Limits> = const { Cell::new(Limits::DEFAULT) };
+}
+
+/// Restores the previous limits when dropped, even if reading panics.
+struct Scope {
+    previous: Limits,
+}
+
+impl Limits {
+    /// The limits used when no others have been specified.
+    pub const DEFAULT: Self = Limits {
+        max_string_len: DEFAULT_MAX_STRING_LEN,
+        max_list_len: DEFAULT_MAX_LIST_LEN,
+        max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+    };
+
+    /// The limits of the current decoding context.
+    pub fn current() -> Self {
+        CURRENT.with(|current| current.get())
+    }
+
+    /// Read a value of type **T** from the given reader with these limits applied.
+    pub fn read<R, T>(&self, mut reader: R) -> io::Result<T>
+        where
+            R: ReadBytesExt,
+            T: ReadFromBytes,
+    {
+        self.scope(|| reader.read_bytes())
+    }
+
+    /// Check that a string of the given length is within the limits.
+    pub fn check_string_len(&self, len: usize) -> io::Result<()> {
+        check("string length", len, self.max_string_len)
+    }
+
+    /// Check that a list of the given length is within the limits.
+    pub fn check_list_len(&self, len: usize) -> io::Result<()> {
+        check("list length", len, self.max_list_len)
+    }
+
+    /// Check that the given CITP `message_size` is within the limits.
+    pub fn check_message_size(&self, message_size: u32) -> io::Result<()> {
+        check("message size", message_size as usize, self.max_message_size)
+    }
+
+    /// The limits bounded by the given number of remaining bytes.
+    ///
+    /// Every string and list element occupies at least one byte, so neither may be longer than the
+    /// number of bytes that remain.
+    fn bounded_by(&self, remaining: usize) -> Self {
+        Limits {
+            max_string_len: self.max_string_len.min(remaining),
+            max_list_len: self.max_list_len.min(remaining),
+            max_message_size: self.max_message_size,
+        }
+    }
+
+    /// Call the given function with these limits as the current decoding context.
+    fn scope<F, T>(&self, f: F) -> T
+        where
+            F: FnOnce() -> T,
+    {
+        let previous = CURRENT.with(|current| current.replace(*self));
+        let _scope = Scope { previous };
+        f()
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::DEFAULT
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.previous));
+    }
+}
+
+/// Read the body of a message whose headers of `headers_size` bytes have already been read.
+///
+/// The reader is limited to the remainder of the message as described by the `message_size` of
+/// the CITP header and the current limits are bounded by the number of bytes that remain. Any
+/// trailing data not consumed by `read` (e.g. fields appended by a later version of the protocol)
+/// is skipped.
+pub(crate) fn read_message_body<R, F, T>(
+    reader: R,
+    message_size: u32,
+    headers_size: usize,
+    read: F,
+) -> io::Result<T>
+    where
+        R: ReadBytesExt,
+        F: FnOnce(&mut io::Take<R>) -> io::Result<T>,
+{
+    let limits = Limits::current();
+    limits.check_message_size(message_size)?;
+    let remaining = match (message_size as usize).checked_sub(headers_size) {
+        Some(remaining) => remaining,
+        None => return Err(Error::LengthMismatch { message_size, length: headers_size }.into()),
+    };
+    let mut reader = reader.take(remaining as u64);
+    let body = limits.bounded_by(remaining).scope(|| read(&mut reader))?;
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(body)
+}
+
+/// Read the body of a message from the given slice whose headers of `headers_size` bytes have
+/// already been read.
+///
+/// Behaves the same as **read_message_body**, advancing the slice to the end of the message.
+pub(crate) fn read_message_body_slice<'a, F, T>(
+    bytes: &mut &'a [u8],
+    message_size: u32,
+    headers_size: usize,
+    read: F,
+) -> io::Result<T>
+    where
+        F: FnOnce(&mut &'a [u8]) -> io::Result<T>,
+{
+    let limits = Limits::current();
+    limits.check_message_size(message_size)?;
+    let remaining = match (message_size as usize).checked_sub(headers_size) {
+        Some(remaining) if remaining <= bytes.len() => remaining,
+        _ => {
+            let length = headers_size + bytes.len();
+            return Err(Error::LengthMismatch { message_size, length }.into());
+        }
+    };
+    let (mut body, rest) = bytes.split_at(remaining);
+    let body = limits.bounded_by(remaining).scope(|| read(&mut body))?;
+    *bytes = rest;
+    Ok(body)
+}
+
+/// Produce an error if the given length exceeds the given maximum.
+fn check(limit: &'static str, length: usize, max: usize) -> io::Result<()> {
+    if length > max {
+        return Err(Error::LimitExceeded { limit, length, max }.into());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_limits_scope() {
+    use std::ffi::CString;
+
+    let limits = Limits { max_string_len: 4, ..Limits::default() };
+    let bytes = b"Long\0Longer\0";
+    let mut reader = &bytes[..];
+    let string: CString = limits.read(&mut reader).unwrap();
+    assert_eq!(string.to_bytes(), b"Long");
+    let err = Error::from(limits.read::<_, CString>(&mut reader).unwrap_err());
+    match err {
+        Error::LimitExceeded { limit, max, .. } => assert_eq!((limit, max), ("string length", 4)),
+        err => panic!("unexpected error: {:?}", err),
+    }
+    assert_eq!(Limits::current(), Limits::default());
+}
+
+#[test]
+fn test_limits_bounded_by_message_size() {
+    use std::borrow::Cow;
+    use crate::protocol::{WriteBytes, fsel};
+
+    let sele = fsel::Sele {
+        complete: 1,
+        reserved: 0,
+        fixture_identifiers: Cow::Borrowed(&[1, 2, 3]),
+    };
+    let mut buffer = vec![];
+    buffer.write_bytes(fsel::Message::new(sele)).unwrap();
+    // Claim that the message contains far more fixtures than the `message_size` allows.
+    let len = buffer.len();
+    buffer[len - 8..len - 6].copy_from_slice(&1000u16.to_le_bytes());
+    let result = (&buffer[..]).read_bytes::<fsel::Message<fsel::Sele>>();
+    match Error::from(result.unwrap_err()) {
+        Error::LimitExceeded { limit, length, max } => {
+            assert_eq!((limit, length, max), ("list length", 1000, 10));
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
@@ -27,13 +27,39 @@
 //! - Match on the `content_type` field to determine the next layer to read.
 //! - Read the header for the second layer.
 //! - Match on the `content_type` field of the second layer to determine what type to read.
+//!
+//! The **Packet** type performs all of these steps via **Packet::read_from_bytes**, producing a
+//! variant for each known message and preserving the raw bytes of any unknown messages.
+//!
+//! ## Reading Without Copying.
+//!
+//! Messages that carry large arrays (e.g. SDMX/ChBk DMX levels, FSEL/Sele fixture identifiers or
+//! MSEX/ELTh, MSEX/EThn and MSEX/StFr image buffers) also implement **ReadFromSlice**. Reading
+//! these from a received `&[u8]` via **ReadSlice** borrows the arrays from the buffer where the
+//! wire layout permits rather than allocating.
+//!
+//! ## Serialization.
+//!
+//! Enabling the `serde` feature implements **Serialize** and **Deserialize** for all message
+//! types, e.g. for logging received messages as JSON. Null-terminated and UCS-2 strings are
+//! serialized as strings and cookies as four character strings.
 
-use std::{fmt, io, mem};
+use std::{error, fmt, io, mem, str};
+use std::borrow::Cow;
+use std::num::NonZeroU16;
 use std::ffi::CString;
-use std::hash::{Hash, Hasher};
+use std::hash::Hash;
+use std::io::Read;
+use std::string::FromUtf16Error;
+
+use crate::error::Error;
 
 pub use byteorder::{LE, ReadBytesExt, WriteBytesExt};
-use byteorder::LittleEndian;
+pub use self::limits::Limits;
+
+pub mod limits;
+#[cfg(feature = "serde")]
+mod serde_impl;
 
 /// ## CITP/PINF - Peer Information Layer
 ///
@@ -52,7 +78,7 @@ pub mod pinf;
 
 /// The SDMX layer is used to transmit DMX information. CITP supports transmitting a single - wide
 /// - universe of DMX channels with at most 65_536 channels. It also supports designating an
-/// alternative DMX source such as ArtNet or ETCNet2 (see "connection strings").
+///   alternative DMX source such as ArtNet or ETCNet2 (see "connection strings").
 pub mod sdmx;
 
 /// ## CITP/FPTC - Fixture patch layer
@@ -161,7 +187,7 @@ pub mod finf;
 /// frames:
 ///
 /// - RGB8 - a raw array of 8-byte RGB triples (this is **not** BMP). In MSEX 1.0 the byte order
-/// was BGR, but from MSEX 1.1 the byte order is RGB.
+///   was BGR, but from MSEX 1.1 the byte order is RGB.
 /// - JPEG - the well known file format (which does **not** include EXIF).
 /// - PNG - the well known file format. Requires MSEX 1.2.
 /// - Fragmented JPB - JPEG data fragments (for streams only). Requires MSEX 1.2.
@@ -182,6 +208,14 @@ pub trait ReadBytes {
     fn read_bytes<P: ReadFromBytes>(&mut self) -> io::Result<P>;
 }
 
+/// A trait for reading CITP protocol types from a byte slice, borrowing from the slice where the
+/// layout of the type permits.
+///
+/// An implementation is provided for `&'a [u8]`. The slice is advanced past the bytes read.
+pub trait ReadSlice<'a> {
+    fn read_slice<P: ReadFromSlice<'a>>(&mut self) -> io::Result<P>;
+}
+
 /// Protocol types that may be written to little endian bytes.
 pub trait WriteToBytes {
     /// Write the command to bytes.
@@ -194,6 +228,21 @@ pub trait ReadFromBytes: Sized {
     fn read_from_bytes<R: ReadBytesExt>(_: R) -> io::Result<Self>;
 }
 
+/// Protocol types that may be read from a slice of little endian bytes without copying.
+///
+/// Fields of type `Cow` are `Cow::Borrowed` from the slice where the wire layout matches the
+/// in-memory layout, e.g. raw DMX levels. Otherwise they are read into `Cow::Owned` data.
+pub trait ReadFromSlice<'a>: Sized {
+    /// Read the command from the front of the slice, advancing the slice past the bytes read.
+    fn read_from_slice(_: &mut &'a [u8]) -> io::Result<Self>;
+}
+
+/// Messages that are identified by a cookie within the `content_type` field of their layer header.
+pub trait ContentType {
+    /// The cookie identifying the message.
+    const CONTENT_TYPE: &'static [u8; 4];
+}
+
 /// Types that have a constant size when written to or read from bytes.
 pub trait ConstSizeBytes: SizeBytes {
     const SIZE_BYTES: usize;
@@ -204,25 +253,26 @@ pub trait SizeBytes {
     fn size_bytes(&self) -> usize;
 }
 
+/// A four character code identifying a layer or message, e.g. `CITP`, `PINF` or `PLoc`.
+///
+/// Cookies are written to bytes in the order that their characters appear, which is equivalent to
+/// the little-endian `u32` used by the specification.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cookie([u8; 4]);
+
 /// The CITP layer provides a standard, single, header used at the start of all CITP packets.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Header {
     /// Set to "CITP"
-    pub cookie: u32,
+    pub cookie: Cookie,
     /// Set to 1.
     pub version_major: u8,
     /// Set to 0.
     pub version_minor: u8,
-    /// These allow request/response message pairs to be better associated and is particularly useful
-    /// for debugging purposes. A node that sends request messages (such as a Lighting Console
-    /// requesting info from a Media Server) should maintain a request counter, and increment this with
-    /// every request message sent. When the other side sends a response to a specific request message,
-    /// it should set this field to the same value as was found in the corresponding request message.
-    ///
-    /// The value of `0` is taken to mean `ignored`, so proper `RequestIndex` values should start
-    /// at `1` (and wrap back around to `1`, avoiding the `0` "ignored" value). This was introduced
-    /// for MSEX 1.2 and was previously a reserved 2-byte alignment field.
+    /// Associates request/response message pairs. Introduced by MSEX 1.2 and previously a reserved
+    /// 2-byte alignment field. See **Kind**.
     pub kind: Kind,
     /// The size of the entire message, including this header.
     pub message_size: u32,
@@ -231,53 +281,244 @@ pub struct Header {
     /// Index of this message fragment (0-based).
     pub message_part: u16,
     /// Cookie identifying the type of contents (the name of the second layer).
-    pub content_type: u32,
+    pub content_type: Cookie,
 }
 
-#[derive(Copy, Clone)]
-#[repr(C)]
-pub union Kind {
-    pub request_index: u16,
-    pub in_response_to: u16,
+/// The request index carried by the `kind` field of the CITP header.
+///
+/// Request indices allow request/response message pairs to be associated. A node that sends
+/// request messages assigns each request a new index, e.g. via a **RequestCounter**, and the
+/// response to a request carries the index of the request. Both are written as the same `u16`
+/// field, with `0` meaning "ignored".
+///
+/// The bytes of a header do not describe whether the index identifies a request or the request
+/// being responded to, so this is determined by the content type of the message. MSEX response
+/// messages (e.g. `ELIn` or `Nack`) are read with a `Kind::InResponseTo` and all other messages
+/// with a `Kind::RequestIndex`. Writing an MSEX message whose kind does not match its content type
+/// is an error.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Kind {
+    /// The field is not used, e.g. by messages that are neither requests nor responses or by
+    /// versions of MSEX prior to 1.2.
+    #[default]
+    Ignored,
+    /// The index of a request message.
+    RequestIndex(NonZeroU16),
+    /// The index of the request to which the message responds.
+    InResponseTo(NonZeroU16),
+}
+
+/// Assigns request indices to outgoing request messages.
+///
+/// Request indices start at `1` and wrap back around to `1`, avoiding the `0` "ignored" value.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RequestCounter {
+    /// The most recently assigned request index, or `0` if none have been assigned.
+    last_request_index: u16,
+}
+
+/// A null-terminated UCS-2 string, as used by all MSEX text fields.
+///
+/// The string is stored as the sequence of 16-bit units that are written to and read from bytes,
+/// excluding the null terminator. Units are preserved exactly as they are read, so strings sent by
+/// peers that use unpaired surrogates or other invalid units may still be written back unchanged.
+///
+/// Strings containing characters outside of the Basic Multilingual Plane are encoded as UTF-16
+/// surrogate pairs, as most implementations treat these strings as UTF-16LE.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ucs2String {
+    units: Vec<u16>,
+}
+
+/// An error indicating that a null unit was found within the data given to `Ucs2String::new` or
+/// `Ucs2String::from_units`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ucs2NulError {
+    position: usize,
+}
+
+/// Any CITP packet, as read by **Packet::read_from_bytes**.
+///
+/// Each variant holds the complete message for its layer, including all headers. Packets whose
+/// layer or message cookie is not recognised are read as **Packet::Unknown** with their raw bytes
+/// preserved, as MSEX requires that unknown messages are ignored rather than treated as errors.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Packet {
+    /// PINF/PNam - Peer Name message.
+    PNam(pinf::Message<pinf::PNam>),
+    /// PINF/PLoc - Peer Location message.
+    PLoc(pinf::Message<pinf::PLoc>),
+    /// SDMX/Capa - Capabilities message.
+    Capa(sdmx::Message<sdmx::Capa<'static>>),
+    /// SDMX/UNam - Universe Name message.
+    UNam(sdmx::Message<sdmx::UNam>),
+    /// SDMX/EnId - Encryption Identifier message.
+    EnId(sdmx::Message<sdmx::EnId>),
+    /// SDMX/ChBk - Channel Block message.
+    ChBk(sdmx::Message<sdmx::ChBk<'static>>),
+    /// SDMX/ChLs - Channel List message.
+    ChLs(sdmx::Message<sdmx::ChLs<'static>>),
+    /// SDMX/SXSr - Set External Source message.
+    SXSr(sdmx::Message<sdmx::SXSr>),
+    /// SDMX/SXUS - Set External Universe Source message.
+    Sxus(sdmx::Message<sdmx::Sxus>),
+    /// FPTC/Ptch - Patch message.
+    Ptch(fptc::Message<fptc::Ptch>),
+    /// FPTC/UPtc - Unpatch message.
+    UPtc(fptc::Message<fptc::UPtc<'static>>),
+    /// FPTC/SPtc - Send Patch message.
+    SPtc(fptc::Message<fptc::SPtc<'static>>),
+    /// FSEL/Sele - Select message.
+    Sele(fsel::Message<fsel::Sele<'static>>),
+    /// FSEL/DeSe - Deselect message.
+    DeSe(fsel::Message<fsel::DeSe<'static>>),
+    /// FINF/SFra - Send Frames message.
+    SFra(finf::Message<finf::SFra<'static>>),
+    /// FINF/Fram - Frames message.
+    Fram(finf::Message<finf::Fram>),
+    /// MSEX/CInf - Client Information message.
+    CInf(msex::Message<msex::CInf<'static>>),
+    /// MSEX/SInf - Server Information message.
+    SInf(msex::Message<msex::SInf<'static>>),
+    /// MSEX/Nack - Negative Acknowledge message.
+    Nack(msex::Message<msex::Nack>),
+    /// MSEX/LSta - Layer Status message.
+    LSta(msex::Message<msex::LSta<'static>>),
+    /// MSEX/GELI - Get Element Library Information message.
+    GELI(msex::Message<msex::GELI<'static>>),
+    /// MSEX/ELIn - Element Library Information message.
+    ELIn(msex::Message<msex::ELIn<'static>>),
+    /// MSEX/ELUp - Element Library Updated message.
+    ELUp(msex::Message<msex::ELUp>),
+    /// MSEX/GEIn - Get Element Information message.
+    GEIn(msex::Message<msex::GEIn<'static>>),
+    /// MSEX/MEIn - Media Element Information message.
+    MEIn(msex::Message<msex::MEIn<'static>>),
+    /// MSEX/EEIn - Effect Element Information message.
+    EEIn(msex::Message<msex::EEIn<'static>>),
+    /// MSEX/GLEI - Generic Element Information message.
+    GLEI(msex::Message<msex::GLEI<'static>>),
+    /// MSEX/GELT - Get Element Library Thumbnail message.
+    GELT(msex::Message<msex::GELT<'static>>),
+    /// MSEX/ELTh - Element Library Thumbnail message.
+    ELTh(msex::Message<msex::ELTh<'static>>),
+    /// MSEX/GETh - Get Element Thumbnail message.
+    GETh(msex::Message<msex::GETh<'static>>),
+    /// MSEX/EThn - Element Thumbnail message.
+    EThn(msex::Message<msex::EThn<'static>>),
+    /// MSEX/GVSr - Get Video Sources message.
+    GVSr(msex::Message<msex::GVSr>),
+    /// MSEX/VSrc - Video Sources message.
+    VSrc(msex::Message<msex::VSrc<'static>>),
+    /// MSEX/RqSt - Request Stream message.
+    RqSt(msex::Message<msex::RqSt>),
+    /// MSEX/StFr - Stream Frame message.
+    StFr(msex::Message<msex::StFr<'static>>),
+    /// A packet with an unrecognised layer or message cookie.
+    Unknown {
+        /// The CITP header at the start of the packet.
+        citp_header: Header,
+        /// The raw bytes of the entire packet, including all headers.
+        bytes: Vec<u8>,
+    },
+}
+
+impl WriteToBytes for Cookie {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
 }
 
 impl WriteToBytes for Kind {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        unsafe { writer.write_u16::<LE>(self.request_index) }
+        writer.write_u16::<LE>(self.to_u16())
     }
 }
 
 impl WriteToBytes for Header {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u32::<LE>(self.cookie)?;
+        writer.write_bytes(self.cookie)?;
         writer.write_u8(self.version_major)?;
         writer.write_u8(self.version_minor)?;
         writer.write_bytes(self.kind)?;
         writer.write_u32::<LE>(self.message_size)?;
         writer.write_u16::<LE>(self.message_part_count)?;
         writer.write_u16::<LE>(self.message_part)?;
-        writer.write_u32::<LE>(self.content_type)?;
+        writer.write_bytes(self.content_type)?;
         Ok(())
     }
 }
 
+impl WriteToBytes for Packet {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        match *self {
+            Packet::PNam(ref msg) => writer.write_bytes(msg),
+            Packet::PLoc(ref msg) => writer.write_bytes(msg),
+            Packet::Capa(ref msg) => writer.write_bytes(msg),
+            Packet::UNam(ref msg) => writer.write_bytes(msg),
+            Packet::EnId(ref msg) => writer.write_bytes(msg),
+            Packet::ChBk(ref msg) => writer.write_bytes(msg),
+            Packet::ChLs(ref msg) => writer.write_bytes(msg),
+            Packet::SXSr(ref msg) => writer.write_bytes(msg),
+            Packet::Sxus(ref msg) => writer.write_bytes(msg),
+            Packet::Ptch(ref msg) => writer.write_bytes(msg),
+            Packet::UPtc(ref msg) => writer.write_bytes(msg),
+            Packet::SPtc(ref msg) => writer.write_bytes(msg),
+            Packet::Sele(ref msg) => writer.write_bytes(msg),
+            Packet::DeSe(ref msg) => writer.write_bytes(msg),
+            Packet::SFra(ref msg) => writer.write_bytes(msg),
+            Packet::Fram(ref msg) => writer.write_bytes(msg),
+            Packet::CInf(ref msg) => writer.write_bytes(msg),
+            Packet::SInf(ref msg) => writer.write_bytes(msg),
+            Packet::Nack(ref msg) => writer.write_bytes(msg),
+            Packet::LSta(ref msg) => writer.write_bytes(msg),
+            Packet::GELI(ref msg) => writer.write_bytes(msg),
+            Packet::ELIn(ref msg) => writer.write_bytes(msg),
+            Packet::ELUp(ref msg) => writer.write_bytes(msg),
+            Packet::GEIn(ref msg) => writer.write_bytes(msg),
+            Packet::MEIn(ref msg) => writer.write_bytes(msg),
+            Packet::EEIn(ref msg) => writer.write_bytes(msg),
+            Packet::GLEI(ref msg) => writer.write_bytes(msg),
+            Packet::GELT(ref msg) => writer.write_bytes(msg),
+            Packet::ELTh(ref msg) => writer.write_bytes(msg),
+            Packet::GETh(ref msg) => writer.write_bytes(msg),
+            Packet::EThn(ref msg) => writer.write_bytes(msg),
+            Packet::GVSr(ref msg) => writer.write_bytes(msg),
+            Packet::VSrc(ref msg) => writer.write_bytes(msg),
+            Packet::RqSt(ref msg) => writer.write_bytes(msg),
+            Packet::StFr(ref msg) => writer.write_bytes(msg),
+            Packet::Unknown { ref bytes, .. } => writer.write_all(bytes),
+        }
+    }
+}
+
+impl ReadFromBytes for Cookie {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let mut bytes = [0u8; 4];
+        reader.read_exact(&mut bytes)?;
+        Ok(Cookie(bytes))
+    }
+}
+
 impl ReadFromBytes for Kind {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
         let request_index = reader.read_u16::<LE>()?;
-        Ok(Kind { request_index })
+        Ok(Kind::from_u16(request_index))
     }
 }
 
 impl ReadFromBytes for Header {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
-        let cookie = reader.read_u32::<LE>()?;
+        let cookie = reader.read_bytes()?;
         let version_major = reader.read_u8()?;
         let version_minor = reader.read_u8()?;
         let kind = reader.read_bytes()?;
         let message_size = reader.read_u32::<LE>()?;
         let message_part_count = reader.read_u16::<LE>()?;
         let message_part = reader.read_u16::<LE>()?;
-        let content_type = reader.read_u32::<LE>()?;
+        let content_type = reader.read_bytes()?;
         let header = Header {
             cookie,
             version_major,
@@ -310,7 +551,13 @@ impl<R> ReadBytes for R
     }
 }
 
-impl<'a, T> WriteToBytes for &'a T
+impl<'a> ReadSlice<'a> for &'a [u8] {
+    fn read_slice<P: ReadFromSlice<'a>>(&mut self) -> io::Result<P> {
+        P::read_from_slice(self)
+    }
+}
+
+impl<T> WriteToBytes for &T
     where
         T: WriteToBytes,
 {
@@ -331,12 +578,14 @@ impl WriteToBytes for CString {
 
 impl ReadFromBytes for CString {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let limits = Limits::current();
         let mut bytes = vec![];
         loop {
             match reader.read_u8()? {
                 b'\0' => break,
                 byte => bytes.push(byte),
             }
+            limits.check_string_len(bytes.len())?;
         }
         let cstring = unsafe { CString::from_vec_unchecked(bytes) };
         Ok(cstring)
@@ -355,52 +604,386 @@ impl ReadFromBytes for u16 {
     }
 }
 
+impl ReadFromBytes for u32 {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        reader.read_u32::<LE>()
+    }
+}
+
+impl WriteToBytes for Ucs2String {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        for &unit in &self.units {
+            writer.write_u16::<LE>(unit)?;
+        }
+        writer.write_u16::<LE>(0)?;
+        Ok(())
+    }
+}
+
+impl ReadFromBytes for Ucs2String {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let limits = Limits::current();
+        let mut units = vec![];
+        loop {
+            match reader.read_u16::<LE>()? {
+                0 => break,
+                unit => units.push(unit),
+            }
+            limits.check_string_len(units.len())?;
+        }
+        Ok(Ucs2String { units })
+    }
+}
+
+impl ReadFromBytes for Packet {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let citp_header: Header = reader.read_bytes()?;
+        check_cookie(citp_header.cookie, Header::COOKIE)?;
+        Limits::current().check_message_size(citp_header.message_size)?;
+        let message_size = citp_header.message_size as usize;
+        let remaining = match message_size.checked_sub(citp_header.size_bytes()) {
+            Some(remaining) => remaining,
+            None => {
+                let length = citp_header.size_bytes();
+                let message_size = citp_header.message_size;
+                return Err(Error::LengthMismatch { message_size, length }.into());
+            }
+        };
+        let mut bytes = Vec::with_capacity(message_size);
+        bytes.write_bytes(citp_header)?;
+        reader.take(remaining as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != message_size {
+            let length = bytes.len();
+            let message_size = citp_header.message_size;
+            return Err(Error::LengthMismatch { message_size, length }.into());
+        }
+        let layer = citp_header.content_type;
+        let packet = if layer == pinf::Header::CONTENT_TYPE {
+            read_pinf_packet(&bytes)?
+        } else if layer == sdmx::Header::CONTENT_TYPE {
+            read_sdmx_packet(&bytes)?
+        } else if layer == fptc::Header::CONTENT_TYPE {
+            read_fptc_packet(&bytes)?
+        } else if layer == fsel::Header::CONTENT_TYPE {
+            read_fsel_packet(&bytes)?
+        } else if layer == finf::Header::CONTENT_TYPE {
+            read_finf_packet(&bytes)?
+        } else if layer == msex::Header::CONTENT_TYPE {
+            read_msex_packet(&bytes)?
+        } else {
+            None
+        };
+        Ok(packet.unwrap_or(Packet::Unknown { citp_header, bytes }))
+    }
+}
+
+impl<T> ContentType for &T
+    where
+        T: ContentType,
+{
+    const CONTENT_TYPE: &'static [u8; 4] = T::CONTENT_TYPE;
+}
+
+impl<T> SizeBytes for &T
+    where
+        T: SizeBytes,
+{
+    fn size_bytes(&self) -> usize {
+        (**self).size_bytes()
+    }
+}
+
 impl SizeBytes for CString {
     fn size_bytes(&self) -> usize {
         self.as_bytes_with_nul().len()
     }
 }
 
+impl SizeBytes for Ucs2String {
+    fn size_bytes(&self) -> usize {
+        (self.units.len() + 1) * mem::size_of::<u16>()
+    }
+}
+
+impl SizeBytes for Cookie {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<Cookie>()
+    }
+}
+
 impl SizeBytes for Kind {
     fn size_bytes(&self) -> usize {
-        mem::size_of::<Kind>()
+        mem::size_of::<u16>()
     }
 }
 
 impl SizeBytes for Header {
     fn size_bytes(&self) -> usize {
-        mem::size_of::<Header>()
+        self.cookie.size_bytes()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + self.kind.size_bytes()
+            + mem::size_of::<u32>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + self.content_type.size_bytes()
+    }
+}
+
+impl Cookie {
+    /// Create a cookie from its four characters, e.g. `Cookie::new(b"PINF")`.
+    pub const fn new(bytes: &[u8; 4]) -> Self {
+        Cookie(*bytes)
+    }
+
+    /// The four characters of the cookie.
+    pub fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+
+    /// Create a cookie from its little-endian `u32` representation.
+    pub fn from_u32(cookie: u32) -> Self {
+        Cookie(cookie.to_le_bytes())
+    }
+
+    /// The little-endian `u32` representation of the cookie.
+    pub fn to_u32(self) -> u32 {
+        u32::from_le_bytes(self.0)
     }
 }
 
-impl fmt::Debug for Kind {
+impl From<[u8; 4]> for Cookie {
+    fn from(bytes: [u8; 4]) -> Self {
+        Cookie(bytes)
+    }
+}
+
+impl From<Cookie> for [u8; 4] {
+    fn from(cookie: Cookie) -> Self {
+        cookie.0
+    }
+}
+
+impl PartialEq<[u8; 4]> for Cookie {
+    fn eq(&self, other: &[u8; 4]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<'a> PartialEq<&'a [u8; 4]> for Cookie {
+    fn eq(&self, other: &&'a [u8; 4]) -> bool {
+        self.0 == **other
+    }
+}
+
+impl fmt::Display for Cookie {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe { write!(f, "{:?}", self.request_index) }
+        for &byte in &self.0 {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                write!(f, "{}", byte as char)?;
+            } else {
+                write!(f, "\\x{:02x}", byte)?;
+            }
+        }
+        Ok(())
     }
 }
 
-impl Eq for Kind {}
+impl fmt::Debug for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cookie(\"{}\")", self)
+    }
+}
+
+impl Kind {
+    /// The kind described by the given value of the `kind` field, as read from bytes.
+    pub const fn from_u16(value: u16) -> Self {
+        match NonZeroU16::new(value) {
+            Some(request_index) => Kind::RequestIndex(request_index),
+            None => Kind::Ignored,
+        }
+    }
+
+    /// The value of the `kind` field, as written to bytes.
+    pub const fn to_u16(self) -> u16 {
+        match self {
+            Kind::Ignored => 0,
+            Kind::RequestIndex(index) | Kind::InResponseTo(index) => index.get(),
+        }
+    }
 
-impl PartialEq for Kind {
-    fn eq(&self, other: &Self) -> bool {
-        unsafe { self.request_index == other.request_index }
+    /// The index of the request, if this is the kind of a request message.
+    pub fn request_index(self) -> Option<NonZeroU16> {
+        match self {
+            Kind::RequestIndex(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// The index of the request being responded to, if this is the kind of a response message.
+    pub fn in_response_to(self) -> Option<NonZeroU16> {
+        match self {
+            Kind::InResponseTo(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// The kind of a response to a request of this kind.
+    ///
+    /// A `RequestIndex` becomes an `InResponseTo` with the same index. Other kinds are unchanged.
+    pub fn into_response(self) -> Self {
+        match self {
+            Kind::RequestIndex(index) => Kind::InResponseTo(index),
+            kind => kind,
+        }
     }
 }
 
-impl Hash for Kind {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        unsafe {
-            self.request_index.hash(state);
+impl RequestCounter {
+    /// Create a new counter whose first request index is `1`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assign the next request index, producing the kind to be used within the CITP header of the
+    /// request.
+    pub fn next_request(&mut self) -> Kind {
+        self.last_request_index = match self.last_request_index.wrapping_add(1) {
+            0 => 1,
+            request_index => request_index,
+        };
+        Kind::from_u16(self.last_request_index)
+    }
+}
+
+impl Ucs2String {
+    /// Encode the given string as UCS-2.
+    ///
+    /// Returns an error if the string contains a null character.
+    pub fn new<S: AsRef<str>>(string: S) -> Result<Self, Ucs2NulError> {
+        Self::from_units(string.as_ref().encode_utf16().collect())
+    }
+
+    /// Create a string from the given 16-bit units, excluding the null terminator.
+    ///
+    /// Returns an error if the units contain a null unit.
+    pub fn from_units(units: Vec<u16>) -> Result<Self, Ucs2NulError> {
+        match units.iter().position(|&unit| unit == 0) {
+            Some(position) => Err(Ucs2NulError { position }),
+            None => Ok(Ucs2String { units }),
+        }
+    }
+
+    /// The 16-bit units of the string, excluding the null terminator.
+    pub fn as_units(&self) -> &[u16] {
+        &self.units
+    }
+
+    /// The number of 16-bit units in the string, excluding the null terminator.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Whether or not the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Whether or not the string is strictly UCS-2, i.e. contains no surrogate units.
+    pub fn is_ucs2(&self) -> bool {
+        self.units.iter().all(|unit| !(0xD800..=0xDFFF).contains(unit))
+    }
+
+    /// Decode the string, returning an error if it contains unpaired surrogates.
+    pub fn into_string(self) -> Result<String, FromUtf16Error> {
+        String::from_utf16(&self.units)
+    }
+
+    /// Decode the string, replacing unpaired surrogates with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(&self.units)
+    }
+}
+
+impl Ucs2NulError {
+    /// The index of the null unit within the encoded string.
+    pub fn nul_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl str::FromStr for Ucs2String {
+    type Err = Ucs2NulError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for Ucs2String {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in std::char::decode_utf16(self.units.iter().cloned()) {
+            write!(f, "{}", c.unwrap_or(std::char::REPLACEMENT_CHARACTER))?;
         }
+        Ok(())
     }
 }
 
+impl fmt::Display for Ucs2NulError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "null unit found in UCS-2 string at position {}", self.position)
+    }
+}
+
+impl error::Error for Ucs2NulError {}
+
+impl From<Ucs2NulError> for Error {
+    fn from(_: Ucs2NulError) -> Self {
+        Error::InvalidString("null unit found in UCS-2 string")
+    }
+}
+
+impl From<Ucs2NulError> for io::Error {
+    fn from(err: Ucs2NulError) -> Self {
+        Error::from(err).into()
+    }
+}
+
+/// Check that a cookie read from a header matches the expected cookie.
+pub fn check_cookie(cookie: Cookie, expected: &[u8; 4]) -> io::Result<()> {
+    if cookie == expected {
+        return Ok(());
+    }
+    let expected = Cookie::new(expected);
+    Err(Error::UnexpectedCookie { expected, found: cookie }.into())
+}
+
+/// Write a `u8` element count, checking that the given length does not exceed `u8::MAX`.
+pub(crate) fn write_count_u8<W: WriteBytesExt>(mut writer: W, len: usize) -> io::Result<()> {
+    if len > u8::MAX as usize {
+        let max = u8::MAX as usize;
+        return Err(Error::CountOverflow { count: len, max }.into());
+    }
+    writer.write_u8(len as u8)
+}
+
+/// Write a `u16` element count, checking that the given length does not exceed `u16::MAX`.
+pub(crate) fn write_count_u16<W: WriteBytesExt>(mut writer: W, len: usize) -> io::Result<()> {
+    if len > u16::MAX as usize {
+        let max = u16::MAX as usize;
+        return Err(Error::CountOverflow { count: len, max }.into());
+    }
+    writer.write_u16::<LE>(len as u16)
+}
+
 /// Read **len** elements of type **T** into the given **vec**.
+///
+/// Returns an error if **len** exceeds the `max_list_len` of the current **Limits**.
 pub fn read_vec<R, T>(mut reader: R, mut len: usize, vec: &mut Vec<T>) -> io::Result<()>
     where
         R: ReadBytesExt,
         T: ReadFromBytes,
 {
+    Limits::current().check_list_len(len)?;
     while len > 0 {
         let elem = reader.read_bytes()?;
         vec.push(elem);
@@ -410,23 +993,174 @@ pub fn read_vec<R, T>(mut reader: R, mut len: usize, vec: &mut Vec<T>) -> io::Re
 }
 
 /// Read **len** elements of type **T** into a new **Vec**.
+///
+/// Returns an error if **len** exceeds the `max_list_len` of the current **Limits**, before any
+/// memory is allocated for the elements.
 pub fn read_new_vec<R, T>(reader: R, len: usize) -> io::Result<Vec<T>>
     where
         R: ReadBytesExt,
         T: ReadFromBytes,
 {
+    Limits::current().check_list_len(len)?;
     let mut vec = Vec::with_capacity(len);
     read_vec(reader, len, &mut vec)?;
     Ok(vec)
 }
 
+/// Split **len** bytes from the front of the given slice, advancing the slice past them.
+///
+/// Returns an error if **len** exceeds the `max_list_len` of the current **Limits**.
+pub fn read_borrowed_bytes<'a>(bytes: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    Limits::current().check_list_len(len)?;
+    split_front(bytes, len)
+}
+
+/// Read **len** `u16` elements from the front of the given slice.
+///
+/// The elements are borrowed from the slice if the target is little-endian and the elements are
+/// suitably aligned. Otherwise they are copied into a new **Vec**.
+pub fn read_cow_u16s<'a>(bytes: &mut &'a [u8], len: usize) -> io::Result<Cow<'a, [u16]>> {
+    Limits::current().check_list_len(len)?;
+    let byte_len = len.saturating_mul(mem::size_of::<u16>());
+    let front = split_front(bytes, byte_len)?;
+    if cfg!(target_endian = "little") {
+        // Safety: all bit patterns are valid `u16` values.
+        let (prefix, units, _) = unsafe { front.align_to::<u16>() };
+        if prefix.is_empty() {
+            return Ok(Cow::Borrowed(units));
+        }
+    }
+    let units = front
+        .chunks_exact(mem::size_of::<u16>())
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+    Ok(Cow::Owned(units))
+}
+
+/// Split **len** bytes from the front of the given slice.
+fn split_front<'a>(bytes: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if bytes.len() < len {
+        let err_msg = "failed to fill whole buffer";
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, err_msg));
+    }
+    let (front, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(front)
+}
+
+/// Read a PINF packet, returning `None` if the message cookie is not recognised.
+fn read_pinf_packet(bytes: &[u8]) -> io::Result<Option<Packet>> {
+    let pinf_header: pinf::Header = (&bytes[..]).read_bytes()?;
+    let mut reader = bytes;
+    let packet = match pinf_header.content_type {
+        ct if ct == pinf::PNam::CONTENT_TYPE => Packet::PNam(reader.read_bytes()?),
+        ct if ct == pinf::PLoc::CONTENT_TYPE => Packet::PLoc(reader.read_bytes()?),
+        _ => return Ok(None),
+    };
+    Ok(Some(packet))
+}
+
+/// Read an SDMX packet, returning `None` if the message cookie is not recognised.
+fn read_sdmx_packet(bytes: &[u8]) -> io::Result<Option<Packet>> {
+    let sdmx_header: sdmx::Header = (&bytes[..]).read_bytes()?;
+    let mut reader = bytes;
+    let packet = match sdmx_header.content_type {
+        ct if ct == sdmx::Capa::CONTENT_TYPE => Packet::Capa(reader.read_bytes()?),
+        ct if ct == sdmx::UNam::CONTENT_TYPE => Packet::UNam(reader.read_bytes()?),
+        ct if ct == sdmx::EnId::CONTENT_TYPE => Packet::EnId(reader.read_bytes()?),
+        ct if ct == sdmx::ChBk::CONTENT_TYPE => Packet::ChBk(reader.read_bytes()?),
+        ct if ct == sdmx::ChLs::CONTENT_TYPE => Packet::ChLs(reader.read_bytes()?),
+        ct if ct == sdmx::SXSr::CONTENT_TYPE => Packet::SXSr(reader.read_bytes()?),
+        ct if ct == sdmx::Sxus::CONTENT_TYPE => Packet::Sxus(reader.read_bytes()?),
+        _ => return Ok(None),
+    };
+    Ok(Some(packet))
+}
+
+/// Read an FPTC packet, returning `None` if the message cookie is not recognised.
+fn read_fptc_packet(bytes: &[u8]) -> io::Result<Option<Packet>> {
+    let fptc_header: fptc::Header = (&bytes[..]).read_bytes()?;
+    let mut reader = bytes;
+    let packet = match fptc_header.content_type {
+        ct if ct == fptc::Ptch::CONTENT_TYPE => Packet::Ptch(reader.read_bytes()?),
+        ct if ct == fptc::UPtc::CONTENT_TYPE => Packet::UPtc(reader.read_bytes()?),
+        ct if ct == fptc::SPtc::CONTENT_TYPE => Packet::SPtc(reader.read_bytes()?),
+        _ => return Ok(None),
+    };
+    Ok(Some(packet))
+}
+
+/// Read an FSEL packet, returning `None` if the message cookie is not recognised.
+fn read_fsel_packet(bytes: &[u8]) -> io::Result<Option<Packet>> {
+    let fsel_header: fsel::Header = (&bytes[..]).read_bytes()?;
+    let mut reader = bytes;
+    let packet = match fsel_header.content_type {
+        ct if ct == fsel::Sele::CONTENT_TYPE => Packet::Sele(reader.read_bytes()?),
+        ct if ct == fsel::DeSe::CONTENT_TYPE => Packet::DeSe(reader.read_bytes()?),
+        _ => return Ok(None),
+    };
+    Ok(Some(packet))
+}
+
+/// Read an FINF packet, returning `None` if the message cookie is not recognised.
+fn read_finf_packet(bytes: &[u8]) -> io::Result<Option<Packet>> {
+    let finf_header: finf::Header = (&bytes[..]).read_bytes()?;
+    let mut reader = bytes;
+    let packet = match finf_header.content_type {
+        ct if ct == finf::SFra::CONTENT_TYPE => Packet::SFra(reader.read_bytes()?),
+        ct if ct == finf::Fram::CONTENT_TYPE => Packet::Fram(reader.read_bytes()?),
+        _ => return Ok(None),
+    };
+    Ok(Some(packet))
+}
+
+/// Read an MSEX packet, returning `None` if the message cookie is not recognised.
+fn read_msex_packet(bytes: &[u8]) -> io::Result<Option<Packet>> {
+    let msex_header: msex::Header = (&bytes[..]).read_bytes()?;
+    let mut reader = bytes;
+    let packet = match msex_header.content_type {
+        ct if ct == msex::CInf::CONTENT_TYPE => Packet::CInf(reader.read_bytes()?),
+        ct if ct == msex::SInf::CONTENT_TYPE => Packet::SInf(reader.read_bytes()?),
+        ct if ct == msex::Nack::CONTENT_TYPE => Packet::Nack(reader.read_bytes()?),
+        ct if ct == msex::LSta::CONTENT_TYPE => Packet::LSta(reader.read_bytes()?),
+        ct if ct == msex::GELI::CONTENT_TYPE => Packet::GELI(reader.read_bytes()?),
+        ct if ct == msex::ELIn::CONTENT_TYPE => Packet::ELIn(reader.read_bytes()?),
+        ct if ct == msex::ELUp::CONTENT_TYPE => Packet::ELUp(reader.read_bytes()?),
+        ct if ct == msex::GEIn::CONTENT_TYPE => Packet::GEIn(reader.read_bytes()?),
+        ct if ct == msex::MEIn::CONTENT_TYPE => Packet::MEIn(reader.read_bytes()?),
+        ct if ct == msex::EEIn::CONTENT_TYPE => Packet::EEIn(reader.read_bytes()?),
+        ct if ct == msex::GLEI::CONTENT_TYPE => Packet::GLEI(reader.read_bytes()?),
+        ct if ct == msex::GELT::CONTENT_TYPE => Packet::GELT(reader.read_bytes()?),
+        ct if ct == msex::ELTh::CONTENT_TYPE => Packet::ELTh(reader.read_bytes()?),
+        ct if ct == msex::GETh::CONTENT_TYPE => Packet::GETh(reader.read_bytes()?),
+        ct if ct == msex::EThn::CONTENT_TYPE => Packet::EThn(reader.read_bytes()?),
+        ct if ct == msex::GVSr::CONTENT_TYPE => Packet::GVSr(reader.read_bytes()?),
+        ct if ct == msex::VSrc::CONTENT_TYPE => Packet::VSrc(reader.read_bytes()?),
+        ct if ct == msex::RqSt::CONTENT_TYPE => Packet::RqSt(reader.read_bytes()?),
+        ct if ct == msex::StFr::CONTENT_TYPE => Packet::StFr(reader.read_bytes()?),
+        _ => return Ok(None),
+    };
+    Ok(Some(packet))
+}
+
 impl Header {
     pub const COOKIE: &'static [u8; 4] = b"CITP";
-}
 
-impl Kind {
-    pub fn default() -> Self {
-        return Kind { request_index: 0 };
+    /// Create a CITP version 1.0 header for a single part message.
+    ///
+    /// `content_type` is the cookie of the second layer and `message_size` is the size of the
+    /// entire message including this header.
+    pub fn new(content_type: Cookie, kind: Kind, message_size: u32) -> Self {
+        Header {
+            cookie: Cookie::new(Self::COOKIE),
+            version_major: 1,
+            version_minor: 0,
+            kind,
+            message_size,
+            message_part_count: 1,
+            message_part: 0,
+            content_type,
+        }
     }
 }
 
@@ -442,20 +1176,20 @@ fn test_citp_header_read_bytes() {
     let citp_header: io::Result<Header> = buffer.as_slice().read_bytes::<Header>();
 
     assert!(citp_header.is_ok());
-    assert_eq!(citp_header.unwrap().cookie.to_le_bytes(), *Header::COOKIE);
+    assert_eq!(citp_header.unwrap().cookie, Header::COOKIE);
 }
 
 #[test]
 fn test_citp_header_write_bytes() {
     let citp_header = Header {
-        cookie: Header::COOKIE.as_slice().read_u32::<LittleEndian>().unwrap(),
+        cookie: Cookie::new(Header::COOKIE),
         version_major: 1,
         version_minor: 0,
         kind: Kind::default(),
         message_size: 96,
         message_part_count: 1,
         message_part: 0,
-        content_type: b"PINF".as_slice().read_u32::<LittleEndian>().unwrap(),
+        content_type: Cookie::new(b"PINF"),
     };
 
     let mut vec = vec!();
@@ -470,3 +1204,138 @@ fn test_citp_header_write_bytes() {
     assert!(result.is_ok());
     assert_eq!(vec.as_slice(), expected);
 }
+
+#[test]
+fn test_ucs2_string_write_read_bytes() {
+    let string = Ucs2String::new("Caf\u{e9} \u{1F3AC}").unwrap();
+    assert!(!string.is_ucs2());
+    assert_eq!(string.len(), 7);
+
+    let mut bytes = vec![];
+    bytes.write_bytes(&string).unwrap();
+    assert_eq!(bytes.len(), string.size_bytes());
+    assert_eq!(&bytes[..4], &[b'C', 0, b'a', 0]);
+    assert_eq!(&bytes[bytes.len() - 2..], &[0, 0]);
+
+    let read: Ucs2String = bytes.as_slice().read_bytes().unwrap();
+    assert_eq!(read, string);
+    assert_eq!(read.into_string().unwrap(), "Caf\u{e9} \u{1F3AC}");
+}
+
+#[test]
+fn test_ucs2_string_invalid_units() {
+    assert_eq!(Ucs2String::new("a\0b").unwrap_err().nul_position(), 1);
+
+    // Unpaired surrogates are preserved, but can only be decoded lossily.
+    let string = Ucs2String::from_units(vec![0x61, 0xD800, 0x62]).unwrap();
+    assert_eq!(string.to_string_lossy(), "a\u{FFFD}b");
+    assert_eq!(string.to_string(), "a\u{FFFD}b");
+    assert!(string.into_string().is_err());
+}
+
+#[test]
+fn test_packet_read_bytes() {
+    let citp_header = Header {
+        cookie: Cookie::new(Header::COOKIE),
+        version_major: 1,
+        version_minor: 0,
+        kind: Kind::default(),
+        message_size: 29,
+        message_part_count: 1,
+        message_part: 0,
+        content_type: Cookie::new(b"PINF"),
+    };
+    let pinf_header = pinf::Header {
+        citp_header,
+        content_type: Cookie::new(b"PNam"),
+    };
+    let name = CString::new("Peer").unwrap();
+    let packet = Packet::PNam(pinf::Message { pinf_header, message: pinf::PNam { name } });
+    let mut buffer = vec![];
+    buffer.write_bytes(&packet).unwrap();
+    assert_eq!(buffer.len(), 29);
+    // Trailing bytes beyond the `message_size` must not be consumed.
+    buffer.extend_from_slice(b"CITP");
+    let mut reader = &buffer[..];
+    assert_eq!(reader.read_bytes::<Packet>().unwrap(), packet);
+    assert_eq!(reader, b"CITP");
+
+    // Unknown message cookies preserve the raw bytes of the packet.
+    buffer.truncate(29);
+    buffer[20..24].copy_from_slice(b"PXyz");
+    match buffer.as_slice().read_bytes::<Packet>().unwrap() {
+        Packet::Unknown { citp_header: header, bytes } => {
+            assert_eq!(header, citp_header);
+            assert_eq!(bytes, buffer);
+        }
+        packet => panic!("expected an unknown packet, found {:?}", packet),
+    }
+}
+
+#[test]
+fn test_cookie_display_and_comparison() {
+    const PINF: Cookie = Cookie::new(b"PINF");
+    assert_eq!(PINF, pinf::Header::CONTENT_TYPE);
+    assert_eq!(PINF.to_u32(), u32::from_le_bytes(*b"PINF"));
+    assert_eq!(Cookie::from_u32(PINF.to_u32()), PINF);
+    assert_eq!(PINF.to_string(), "PINF");
+    assert_eq!(format!("{:?}", Cookie::new(b"A\0\x7fB")), "Cookie(\"A\\x00\\x7fB\")");
+
+    let mut buffer = vec![];
+    buffer.write_bytes(PINF).unwrap();
+    assert_eq!(buffer, b"PINF");
+    assert_eq!(buffer.as_slice().read_bytes::<Cookie>().unwrap(), PINF);
+}
+
+#[test]
+fn test_packet_read_bytes_errors() {
+    let citp_header = Header::new(Cookie::new(pinf::Header::CONTENT_TYPE), Kind::default(), 40);
+    let mut buffer = vec![];
+    buffer.write_bytes(citp_header).unwrap();
+    buffer[..4].copy_from_slice(b"CIPT");
+    match Error::from(buffer.as_slice().read_bytes::<Packet>().unwrap_err()) {
+        Error::UnexpectedCookie { expected, found } => {
+            assert_eq!(expected, Header::COOKIE);
+            assert_eq!(found, b"CIPT");
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    buffer[..4].copy_from_slice(Header::COOKIE);
+    match Error::from(buffer.as_slice().read_bytes::<Packet>().unwrap_err()) {
+        Error::LengthMismatch { message_size, length } => {
+            assert_eq!((message_size, length), (40, 20));
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    let err = Error::from(write_count_u8(vec![], 256).unwrap_err());
+    assert!(err.is_protocol_error());
+}
+
+#[test]
+fn test_kind_and_request_counter() {
+    let mut counter = RequestCounter::new();
+    let kind = counter.next_request();
+    assert_eq!(kind.request_index().map(|index| index.get()), Some(1));
+    assert_eq!(kind.in_response_to(), None);
+    let response = kind.into_response();
+    assert_eq!(response.in_response_to(), kind.request_index());
+    assert_eq!(response.to_u16(), kind.to_u16());
+    assert_eq!(Kind::Ignored.into_response(), Kind::Ignored);
+    assert_eq!(Kind::from_u16(0), Kind::Ignored);
+
+    // Both requests and responses are written as the same `u16`.
+    for &kind in &[Kind::Ignored, kind, response] {
+        let header = Header::new(Cookie::new(pinf::Header::CONTENT_TYPE), kind, 20);
+        let mut buffer = vec![];
+        buffer.write_bytes(header).unwrap();
+        assert_eq!(buffer.len(), header.size_bytes());
+        assert_eq!(&buffer[6..8], &kind.to_u16().to_le_bytes());
+    }
+
+    // Request indices wrap around to `1`, skipping the `0` "ignored" value.
+    counter.last_request_index = u16::MAX - 1;
+    let indices: Vec<u16> = (0..3).map(|_| counter.next_request().to_u16()).collect();
+    assert_eq!(indices, vec![u16::MAX, 1, 2]);
+}
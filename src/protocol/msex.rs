@@ -1,9 +1,57 @@
+use std::{fmt, io, mem};
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::CString;
 
-use protocol::{
-    self
+use crate::error::Error;
+use crate::protocol::{
+    self, ContentType, Cookie, LE, Limits, ReadBytes, ReadBytesExt, ReadFromBytes, ReadFromSlice,
+    SizeBytes, Ucs2String, WriteBytes, WriteBytesExt, WriteToBytes, limits, write_count_u16,
+    write_count_u8,
 };
 
+/// Library type of media (images and video).
+pub const LIBRARY_TYPE_MEDIA: u8 = 1;
+
+/// Library type of effects.
+pub const LIBRARY_TYPE_EFFECTS: u8 = 2;
+
+/// Library type of cues.
+pub const LIBRARY_TYPE_CUES: u8 = 3;
+
+/// Library type of crossfades.
+pub const LIBRARY_TYPE_CROSSFADES: u8 = 4;
+
+/// Library type of masks.
+pub const LIBRARY_TYPE_MASKS: u8 = 5;
+
+/// Library type of blend presets.
+pub const LIBRARY_TYPE_BLEND_PRESETS: u8 = 6;
+
+/// Library type of effect presets.
+pub const LIBRARY_TYPE_EFFECT_PRESETS: u8 = 7;
+
+/// Library type of image presets.
+pub const LIBRARY_TYPE_IMAGE_PRESETS: u8 = 8;
+
+/// Library type of 3D meshes.
+pub const LIBRARY_TYPE_3D_MESHES: u8 = 9;
+
+/// Image format cookie for a raw array of 8-bit RGB triples. BGR order prior to MSEX 1.1.
+pub const IMAGE_FORMAT_RGB8: &[u8; 4] = b"RGB8";
+
+/// Image format cookie for JPEG files.
+pub const IMAGE_FORMAT_JPEG: &[u8; 4] = b"JPEG";
+
+/// Image format cookie for PNG files. Requires MSEX 1.2.
+pub const IMAGE_FORMAT_PNG: &[u8; 4] = b"PNG ";
+
+/// Image format cookie for JPEG data fragments. Stream frames only. Requires MSEX 1.2.
+pub const IMAGE_FORMAT_FRAGMENTED_JPEG: &[u8; 4] = b"fJPG";
+
+/// Image format cookie for PNG data fragments. Stream frames only. Requires MSEX 1.2.
+pub const IMAGE_FORMAT_FRAGMENTED_PNG: &[u8; 4] = b"fPNG";
+
 /// The MSEX layer provides a standard, single, header used at the start of all MSEX packets.
 ///
 /// The `content_type` field identifies the specific MSEX message type (e.g. "GETh" for Get Element
@@ -11,18 +59,26 @@ use protocol::{
 /// silently discard the message and not treat this as an error condiion. This is to allow the
 /// specification to continue to evolve over time.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Header {
     /// The CITP header. CITP ContentType is "MSEX".
     pub citp_header: protocol::Header,
+    /// The major MSEX version of the message.
     pub version_major: u8,
+    /// The minor MSEX version of the message.
     pub version_minor: u8,
     /// A cookie defining which MSEX message it is.
-    pub content_type: u32,
+    pub content_type: Cookie,
 }
 
 /// Layout of MSEX messages.
+///
+/// The layout of most MSEX messages depends on the MSEX version in use. When writing or reading a
+/// `Message`, the version described by the `msex_header` is used to determine the layout of the
+/// `message`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Message<T> {
     /// The MSEX header - the base header with the MSEX content type.
@@ -31,6 +87,37 @@ pub struct Message<T> {
     pub message: T,
 }
 
+/// An MSEX version, e.g. `1.2`.
+///
+/// When written as part of a version list (i.e. within `CInf` and `SInf`) the version is
+/// described by a single 2 byte value where the MSB is the major version and the LSB is the minor
+/// version.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+
+/// The `MSEXLibraryId` introduced in MSEX 1.1, identifying a library within a tree of libraries.
+///
+/// The `level` describes the depth of the library within the tree, where `0` refers to the root.
+/// The sub-level fields describe the `0`-based index of the library at each level, with unused
+/// sub-levels set to `0`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct LibraryId {
+    /// `0` - `3`.
+    pub level: u8,
+    /// Sub-level 1 specifier, when depth >= 1.
+    pub sub_level_1: u8,
+    /// Sub-level 2 specifier, when depth >= 2.
+    pub sub_level_2: u8,
+    /// Sub-level 3 specifier, when depth == 3.
+    pub sub_level_3: u8,
+}
+
 /// ## MSEX / CINF - Client Information message
 ///
 /// The Client Information message advises the media server of which versions of MSEX are supported
@@ -43,12 +130,3385 @@ pub struct Message<T> {
 /// understand. Future versions can be defined however, but they must preserve the format of the
 /// previous version and only insert new fields immediately before the FutureMessageData field.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct CInf<'a> {
-    /// Number of following MSEX version pairs.
-    pub supported_msex_versions_count: u8,
-    /// Each 2 byte value is MSB = major MSEX version, LSB = minor MSEX version.
-    pub supported_msex_versions: Cow<'a, [[u8; 2]]>,
+    /// The list of supported MSEX versions.
+    pub supported_msex_versions: Cow<'a, [Version]>,
     /// A hint that future versions of this message may contain trailing data.
+    ///
+    /// As the length of this data is not described by the message itself, it is considered to be
+    /// the remainder of the message as described by the `message_size` of the CITP header.
     pub future_message_data: Cow<'a, [u8]>,
 }
+
+/// ## MSEX / SInf - Server Information message
+///
+/// The Server Information message provides the receiver with product and layer information.
+///
+/// Prior to MSEX 1.2 this message was sent by the media server immediately after a connection was
+/// established. Starting with MSEX 1.2, it is sent in response to a `CInf` message using the
+/// Highest Common MSEX Version.
+///
+/// Fields marked as MSEX 1.2 are not present in the layout used by MSEX 1.0 and 1.1. They are
+/// ignored when writing these earlier versions and are left empty when reading them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SInf<'a> {
+    /// MSEX 1.2 - The UUID of the media server, formatted as an ASCII string, e.g.
+    /// "0daf5c96-33f6-4d9c-8b45-a9c82b3c04b4".
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::uuid"))]
+    pub uuid: [u8; 36],
+    /// The display name of the product.
+    pub product_name: Ucs2String,
+    /// The major version number of the product.
+    pub product_version_major: u8,
+    /// The minor version number of the product.
+    pub product_version_minor: u8,
+    /// MSEX 1.2 - The bugfix version number of the product.
+    pub product_version_bugfix: u8,
+    /// MSEX 1.2 - The list of supported MSEX versions.
+    pub supported_msex_versions: Cow<'a, [Version]>,
+    /// MSEX 1.2 - Bit field of supported library types, where bit `n` represents the library type
+    /// `n + 1`.
+    pub supported_library_types: u16,
+    /// MSEX 1.2 - The cookies of the supported thumbnail formats.
+    pub thumbnail_formats: Cow<'a, [Cookie]>,
+    /// MSEX 1.2 - The cookies of the supported stream formats.
+    pub stream_formats: Cow<'a, [Cookie]>,
+    /// DMX-source connection strings for each layer, as described by the `sdmx::SXSr` message.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstrings"))]
+    pub layer_dmx_sources: Cow<'a, [CString]>,
+}
+
+/// ## MSEX / LSta - Layer Status message
+///
+/// The Layer Status message provides the receiver with information about the current playback
+/// state of each layer. It is sent regularly by the media server - typically 4 or more times per
+/// second - and can also be sent unsolicited whenever the status of a layer changes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LSta<'a> {
+    /// The status of each layer.
+    pub layer_statuses: Cow<'a, [LayerStatus]>,
+}
+
+/// The status of a single layer within a `LSta` message.
+///
+/// Fields marked as MSEX 1.0 or MSEX 1.2 are only present in the layout used by the respective
+/// versions. They are ignored when writing other versions and are left as `0` when reading them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerStatus {
+    /// `0`-based layer number, corresponding to the layers reported in the `SInf` message.
+    pub layer_number: u8,
+    /// Current physical video output index, `0`-based.
+    pub physical_output: u8,
+    /// MSEX 1.0 and 1.1 - The number of the media library in use.
+    pub media_library_number: u8,
+    /// MSEX 1.2 - The type of the media library in use.
+    pub media_library_type: u8,
+    /// MSEX 1.2 - The id of the media library in use.
+    pub media_library_id: LibraryId,
+    /// The number of the media in use within its library.
+    pub media_number: u8,
+    /// The name of the media in use.
+    pub media_name: Ucs2String,
+    /// The current frame number within the media.
+    pub media_position: u32,
+    /// The length of the media in frames.
+    pub media_length: u32,
+    /// The resolution of the media in frames per second.
+    pub media_fps: u8,
+    /// Layer status flags. See the `LayerStatus` associated constants for possible flags.
+    pub layer_status_flags: u32,
+}
+
+/// ## MSEX / Nack - Negative Acknowledge message
+///
+/// The Negative Acknowledge message is sent in response to a request that could not be processed,
+/// e.g. because the request message is not supported or is malformed. The `kind` field of the CITP
+/// header is set to the request index of the refused request. Requires MSEX 1.2.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct Nack {
+    /// The MSEX content type cookie of the refused request.
+    pub received_content_type: Cookie,
+}
+
+/// Tracks outstanding MSEX requests so that replies and `Nack`s can be matched against them.
+///
+/// Each request is assigned a `Kind::RequestIndex` which should be written to the `kind` field of
+/// the request's CITP header. Replies carry the same index as a `Kind::InResponseTo`, allowing
+/// them to be associated with the original request. Requires MSEX 1.2 - earlier versions of MSEX
+/// do not set the `kind` field.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Requests {
+    /// Assigns the request index of each new request.
+    counter: protocol::RequestCounter,
+    /// Content types of outstanding requests, keyed by request index.
+    outstanding: HashMap<u16, Cookie>,
+}
+
+/// A received message matched against an outstanding request by `Requests::response`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Response {
+    /// The message is a reply to the request with the given content type.
+    Reply {
+        request_index: u16,
+        request_content_type: Cookie,
+    },
+    /// The server refused the request with the given content type.
+    Nack {
+        request_index: u16,
+        request_content_type: Cookie,
+    },
+}
+
+/// ## MSEX / GELI - Get Element Library Information message
+///
+/// The Get Element Library Information message is sent to a media server to request information
+/// about its element libraries. The media server responds with an `ELIn` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GELI<'a> {
+    /// The type of the libraries, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// MSEX 1.1 - The id of the parent of the requested libraries.
+    pub library_parent_id: LibraryId,
+    /// The numbers of the requested libraries. Empty to request all available libraries.
+    pub library_numbers: Cow<'a, [u8]>,
+}
+
+/// ## MSEX / ELIn - Element Library Information message
+///
+/// The Element Library Information message describes the element libraries of a media server.
+/// It is sent in response to a `GELI` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ELIn<'a> {
+    /// The type of the libraries, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// Information about each library.
+    pub elements: Cow<'a, [LibraryInformation]>,
+}
+
+/// Information about a single library within an `ELIn` message.
+///
+/// Fields marked with an MSEX version are only present in the layout used by that version and
+/// later (or only that version, for MSEX 1.0). They are ignored when writing other versions and
+/// are left as `0` when reading them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LibraryInformation {
+    /// MSEX 1.0 - The number of the library.
+    pub number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub id: LibraryId,
+    /// MSEX 1.2 - Serial number of the library, incremented whenever the library changes.
+    pub serial_number: u32,
+    /// Minimum DMX value selecting the library.
+    pub dmx_range_min: u8,
+    /// Maximum DMX value selecting the library.
+    pub dmx_range_max: u8,
+    /// The display name of the library.
+    pub name: Ucs2String,
+    /// MSEX 1.1 - The number of sub-libraries. Limited to `u8::MAX` prior to MSEX 1.2.
+    pub library_count: u16,
+    /// The number of elements in the library. Limited to `u8::MAX` prior to MSEX 1.2.
+    pub element_count: u16,
+}
+
+/// ## MSEX / ELUp - Element Library Updated message
+///
+/// The Element Library Updated message is sent unsolicited by a media server whenever the
+/// contents of an element library change.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ELUp {
+    /// The type of the library, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// MSEX 1.0 - The number of the library.
+    pub library_number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub library_id: LibraryId,
+    /// Update flags. See the `ELUp` associated constants for possible flags.
+    pub update_flags: u8,
+    /// MSEX 1.2 - Bit array of the affected element numbers, where bit `n % 8` of byte `n / 8`
+    /// represents element `n`.
+    pub affected_elements: [u8; 32],
+    /// MSEX 1.2 - Bit array of the affected sub-library numbers, laid out as `affected_elements`.
+    pub affected_libraries: [u8; 32],
+}
+
+/// ## MSEX / GEIn - Get Element Information message
+///
+/// The Get Element Information message is sent to a media server to request information about
+/// the elements within a library. Depending on the library type, the media server responds with a
+/// `MEIn`, `EEIn` or `GLEI` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GEIn<'a> {
+    /// The type of the library, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// MSEX 1.0 - The number of the library.
+    pub library_number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub library_id: LibraryId,
+    /// The numbers of the requested elements. Empty to request all elements.
+    pub element_numbers: Cow<'a, [u8]>,
+}
+
+/// ## MSEX / MEIn - Media Element Information message
+///
+/// The Media Element Information message describes the elements of a media library. It is sent
+/// in response to a `GEIn` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MEIn<'a> {
+    /// MSEX 1.0 - The number of the library.
+    pub library_number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub library_id: LibraryId,
+    /// Information about each media element.
+    pub elements: Cow<'a, [MediaInformation]>,
+}
+
+/// Information about a single media element within a `MEIn` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaInformation {
+    /// The number of the element.
+    pub number: u8,
+    /// MSEX 1.2 - Serial number of the element, incremented whenever the element changes.
+    pub serial_number: u32,
+    /// Minimum DMX value selecting the element.
+    pub dmx_range_min: u8,
+    /// Maximum DMX value selecting the element.
+    pub dmx_range_max: u8,
+    /// The display name of the media.
+    pub media_name: Ucs2String,
+    /// Version of the media, as the number of seconds since 1970-01-01 00:00:00 UTC.
+    pub media_version_timestamp: u64,
+    /// Width of the media in pixels.
+    pub media_width: u16,
+    /// Height of the media in pixels.
+    pub media_height: u16,
+    /// The length of the media in frames.
+    pub media_length: u32,
+    /// The resolution of the media in frames per second.
+    pub media_fps: u8,
+}
+
+/// ## MSEX / EEIn - Effect Element Information message
+///
+/// The Effect Element Information message describes the elements of an effect library. It is sent
+/// in response to a `GEIn` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EEIn<'a> {
+    /// MSEX 1.0 - The number of the library.
+    pub library_number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub library_id: LibraryId,
+    /// Information about each effect element.
+    pub elements: Cow<'a, [EffectInformation]>,
+}
+
+/// Information about a single effect element within an `EEIn` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectInformation {
+    /// The number of the element.
+    pub number: u8,
+    /// MSEX 1.2 - Serial number of the element, incremented whenever the element changes.
+    pub serial_number: u32,
+    /// Minimum DMX value selecting the element.
+    pub dmx_range_min: u8,
+    /// Maximum DMX value selecting the element.
+    pub dmx_range_max: u8,
+    /// The display name of the effect.
+    pub effect_name: Ucs2String,
+    /// The display names of each of the effect's parameters.
+    pub effect_parameter_names: Vec<Ucs2String>,
+}
+
+/// ## MSEX / GLEI - Generic Element Information message
+///
+/// The Generic Element Information message describes the elements of libraries other than media
+/// and effect libraries. It is sent in response to a `GEIn` message. Requires MSEX 1.1.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GLEI<'a> {
+    /// The id of the library.
+    pub library_id: LibraryId,
+    /// The type of the library, e.g. `LIBRARY_TYPE_CUES`.
+    pub library_type: u8,
+    /// Information about each element.
+    pub elements: Cow<'a, [GenericInformation]>,
+}
+
+/// Information about a single element within a `GLEI` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenericInformation {
+    /// The number of the element.
+    pub number: u8,
+    /// MSEX 1.2 - Serial number of the element, incremented whenever the element changes.
+    pub serial_number: u32,
+    /// Minimum DMX value selecting the element.
+    pub dmx_range_min: u8,
+    /// Maximum DMX value selecting the element.
+    pub dmx_range_max: u8,
+    /// The display name of the element.
+    pub name: Ucs2String,
+    /// Version of the element, as the number of seconds since 1970-01-01 00:00:00 UTC.
+    pub version_timestamp: u64,
+}
+
+/// ## MSEX / GELT - Get Element Library Thumbnail message
+///
+/// The Get Element Library Thumbnail message is sent to a media server to request thumbnails of
+/// element libraries. The media server responds with an `ELTh` message for each library.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GELT<'a> {
+    /// The cookie of the requested image format, e.g. `IMAGE_FORMAT_JPEG`.
+    pub thumbnail_format: Cookie,
+    /// The preferred thumbnail width in pixels.
+    pub thumbnail_width: u16,
+    /// The preferred thumbnail height in pixels.
+    pub thumbnail_height: u16,
+    /// Thumbnail flags. See the `GELT` associated constants for possible flags.
+    pub thumbnail_flags: u8,
+    /// The type of the libraries, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// MSEX 1.0 - The numbers of the requested libraries. Empty to request all libraries.
+    pub library_numbers: Cow<'a, [u8]>,
+    /// MSEX 1.1 - The ids of the requested libraries. Empty to request all libraries.
+    pub library_ids: Cow<'a, [LibraryId]>,
+}
+
+/// ## MSEX / ELTh - Element Library Thumbnail message
+///
+/// The Element Library Thumbnail message carries the thumbnail of a single element library. It is
+/// sent in response to a `GELT` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ELTh<'a> {
+    /// The type of the library, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// MSEX 1.0 - The number of the library.
+    pub library_number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub library_id: LibraryId,
+    /// The cookie of the image format, e.g. `IMAGE_FORMAT_JPEG`.
+    pub thumbnail_format: Cookie,
+    /// The thumbnail width in pixels.
+    pub thumbnail_width: u16,
+    /// The thumbnail height in pixels.
+    pub thumbnail_height: u16,
+    /// The thumbnail image data.
+    pub thumbnail_buffer: Cow<'a, [u8]>,
+}
+
+/// ## MSEX / GETh - Get Element Thumbnail message
+///
+/// The Get Element Thumbnail message is sent to a media server to request thumbnails of the
+/// elements within a library. The media server responds with an `EThn` message for each element.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GETh<'a> {
+    /// The cookie of the requested image format, e.g. `IMAGE_FORMAT_JPEG`.
+    pub thumbnail_format: Cookie,
+    /// The preferred thumbnail width in pixels.
+    pub thumbnail_width: u16,
+    /// The preferred thumbnail height in pixels.
+    pub thumbnail_height: u16,
+    /// Thumbnail flags. See the `GETh` associated constants for possible flags.
+    pub thumbnail_flags: u8,
+    /// The type of the library, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// MSEX 1.0 - The number of the library.
+    pub library_number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub library_id: LibraryId,
+    /// The numbers of the requested elements. Empty to request all elements.
+    pub element_numbers: Cow<'a, [u8]>,
+}
+
+/// ## MSEX / EThn - Element Thumbnail message
+///
+/// The Element Thumbnail message carries the thumbnail of a single element. It is sent in response
+/// to a `GETh` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EThn<'a> {
+    /// The type of the library, e.g. `LIBRARY_TYPE_MEDIA`.
+    pub library_type: u8,
+    /// MSEX 1.0 - The number of the library.
+    pub library_number: u8,
+    /// MSEX 1.1 - The id of the library.
+    pub library_id: LibraryId,
+    /// The number of the element.
+    pub element_number: u8,
+    /// The cookie of the image format, e.g. `IMAGE_FORMAT_JPEG`.
+    pub thumbnail_format: Cookie,
+    /// The thumbnail width in pixels.
+    pub thumbnail_width: u16,
+    /// The thumbnail height in pixels.
+    pub thumbnail_height: u16,
+    /// The thumbnail image data.
+    pub thumbnail_buffer: Cow<'a, [u8]>,
+}
+
+/// ## MSEX / GVSr - Get Video Sources message
+///
+/// The Get Video Sources message is sent to a media server to request a list of the video sources
+/// available for streaming. The media server responds with a `VSrc` message.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GVSr;
+
+/// ## MSEX / VSrc - Video Sources message
+///
+/// The Video Sources message describes the video sources available for streaming. It is sent in
+/// response to a `GVSr` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VSrc<'a> {
+    /// Information about each video source.
+    pub sources: Cow<'a, [SourceInformation]>,
+}
+
+/// Information about a single video source within a `VSrc` message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceInformation {
+    /// The identifier of the source, used to request a stream via the `RqSt` message.
+    pub source_identifier: u16,
+    /// The display name of the source.
+    pub source_name: Ucs2String,
+    /// The physical output index if applicable, otherwise `0xFF`.
+    pub physical_output: u8,
+    /// The layer number if applicable, otherwise `0xFF`.
+    pub layer_number: u8,
+    /// Source flags. See the `SourceInformation` associated constants for possible flags.
+    pub flags: u16,
+    /// The full width of the source in pixels.
+    pub width: u16,
+    /// The full height of the source in pixels.
+    pub height: u16,
+}
+
+/// ## MSEX / RqSt - Request Stream message
+///
+/// The Request Stream message is sent to a media server to request a stream of video frames from
+/// a source. The media server responds by sending `StFr` messages over the multicast address. The
+/// request must be repeated before the timeout elapses to keep the stream alive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct RqSt {
+    /// The identifier of the requested source.
+    pub source_identifier: u16,
+    /// The cookie of the requested frame format, e.g. `IMAGE_FORMAT_JPEG`.
+    pub frame_format: Cookie,
+    /// The preferred frame width in pixels.
+    pub frame_width: u16,
+    /// The preferred frame height in pixels.
+    pub frame_height: u16,
+    /// The preferred number of frames per second.
+    pub fps: u8,
+    /// The number of seconds to stream for if the request is not repeated.
+    pub timeout: u8,
+}
+
+/// ## MSEX / StFr - Stream Frame message
+///
+/// The Stream Frame message carries a single frame - or a fragment of a frame - of a video stream.
+/// Unlike other MSEX messages, it is sent over the multicast address for all peers to process.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StFr<'a> {
+    /// MSEX 1.2 - The UUID of the media server, formatted as an ASCII string.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::uuid"))]
+    pub media_server_uuid: [u8; 36],
+    /// The identifier of the source.
+    pub source_identifier: u16,
+    /// The cookie of the frame format, e.g. `IMAGE_FORMAT_JPEG`.
+    pub frame_format: Cookie,
+    /// The frame width in pixels.
+    pub frame_width: u16,
+    /// The frame height in pixels.
+    pub frame_height: u16,
+    /// MSEX 1.2 - Describes the fragment carried by the `frame_buffer`.
+    ///
+    /// Must be `Some` if and only if the MSEX version is 1.2 and the `frame_format` is
+    /// `IMAGE_FORMAT_FRAGMENTED_JPEG` or `IMAGE_FORMAT_FRAGMENTED_PNG`. Prior to MSEX 1.2 the
+    /// fragmented frame formats do not exist and such a frame is written as any other.
+    pub fragment: Option<Fragment>,
+    /// The frame image data, or the fragment data in the case of a fragmented frame format.
+    pub frame_buffer: Cow<'a, [u8]>,
+}
+
+/// The preamble at the start of the frame buffer of a `StFr` message using a fragmented frame
+/// format.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct Fragment {
+    /// The index of the frame to which the fragment belongs.
+    pub frame_index: u32,
+    /// The total number of fragments within the frame.
+    pub fragment_count: u16,
+    /// The `0`-based index of the fragment within the frame.
+    pub fragment_index: u16,
+    /// The offset of the fragment data in bytes from the start of the frame.
+    pub fragment_byte_offset: u32,
+}
+
+/// MSEX types that may be written to little endian bytes using the layout of a specific MSEX
+/// version.
+pub trait WriteToBytesVersioned {
+    /// Write the message to bytes using the layout described by the given MSEX version.
+    fn write_to_bytes_versioned<W: WriteBytesExt>(&self, _: W, version: Version) -> io::Result<()>;
+}
+
+/// MSEX types that may be read from little endian bytes using the layout of a specific MSEX
+/// version.
+pub trait ReadFromBytesVersioned: Sized {
+    /// Read the message from bytes using the layout described by the given MSEX version.
+    fn read_from_bytes_versioned<R: ReadBytesExt>(_: R, version: Version) -> io::Result<Self>;
+}
+
+/// MSEX types that may be read from a slice of little endian bytes using the layout of a specific
+/// MSEX version, borrowing from the slice where the layout permits.
+pub trait ReadFromSliceVersioned<'a>: Sized {
+    /// Read the message from the front of the slice using the layout described by the given MSEX
+    /// version, advancing the slice past the bytes read.
+    fn read_from_slice_versioned(_: &mut &'a [u8], version: Version) -> io::Result<Self>;
+}
+
+/// MSEX types whose size when written to bytes depends on the MSEX version.
+pub trait SizeBytesVersioned {
+    fn size_bytes_versioned(&self, version: Version) -> usize;
+}
+
+impl Header {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"MSEX";
+
+    /// The content types of messages sent in response to a request.
+    ///
+    /// The `kind` of the CITP header of these messages is read as a `Kind::InResponseTo` and may
+    /// not be written as a `Kind::RequestIndex`.
+    pub const RESPONSE_CONTENT_TYPES: &'static [&'static [u8; 4]] = &[
+        SInf::CONTENT_TYPE,
+        Nack::CONTENT_TYPE,
+        ELIn::CONTENT_TYPE,
+        MEIn::CONTENT_TYPE,
+        EEIn::CONTENT_TYPE,
+        GLEI::CONTENT_TYPE,
+        ELTh::CONTENT_TYPE,
+        EThn::CONTENT_TYPE,
+        VSrc::CONTENT_TYPE,
+    ];
+
+    /// Whether or not the `content_type` describes a message sent in response to a request.
+    pub fn is_response(&self) -> bool {
+        Self::RESPONSE_CONTENT_TYPES.iter().any(|&ct| self.content_type == ct)
+    }
+
+    /// Check that the `kind` of the CITP header matches the variant implied by the `content_type`,
+    /// so that the header is read back unchanged.
+    fn check_kind(&self) -> io::Result<()> {
+        match (self.citp_header.kind, self.is_response()) {
+            (protocol::Kind::RequestIndex(_), true) => {
+                let err_msg = "an MSEX response must not have a `Kind::RequestIndex`";
+                Err(Error::InvalidMessage(err_msg).into())
+            }
+            (protocol::Kind::InResponseTo(_), false) => {
+                let err_msg = "only MSEX responses may have a `Kind::InResponseTo`";
+                Err(Error::InvalidMessage(err_msg).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The MSEX version described by the `version_major` and `version_minor` fields.
+    pub fn version(&self) -> Version {
+        Version {
+            major: self.version_major,
+            minor: self.version_minor,
+        }
+    }
+}
+
+impl Version {
+    pub const V1_0: Self = Version { major: 1, minor: 0 };
+    pub const V1_1: Self = Version { major: 1, minor: 1 };
+    pub const V1_2: Self = Version { major: 1, minor: 2 };
+}
+
+impl<'a> CInf<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"CInf";
+}
+
+impl<'a> SInf<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"SInf";
+
+    /// Whether or not the `supported_library_types` bit field contains the given library type.
+    pub fn supports_library_type(&self, library_type: u8) -> bool {
+        match library_type {
+            1..=16 => self.supported_library_types & (1 << (library_type - 1)) != 0,
+            _ => false,
+        }
+    }
+}
+
+impl<'a> LSta<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"LSta";
+}
+
+impl Nack {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"Nack";
+}
+
+impl Requests {
+    /// Create a new, empty set of outstanding requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new outstanding request with the given MSEX content type.
+    ///
+    /// Returns the `Kind` that must be used within the CITP header of the request. Request indices
+    /// start at `1` and wrap back around to `1`, avoiding the `0` "ignored" value.
+    pub fn request(&mut self, content_type: Cookie) -> protocol::Kind {
+        let kind = self.counter.next_request();
+        self.outstanding.insert(kind.to_u16(), content_type);
+        kind
+    }
+
+    /// Match the header of a received message against the outstanding requests.
+    ///
+    /// If the message is a response to an outstanding request, the request is no longer considered
+    /// outstanding. Returns `None` for unsolicited messages, e.g. `LSta`.
+    pub fn response(&mut self, header: &Header) -> Option<Response> {
+        let request_index = header.citp_header.kind.in_response_to()?.get();
+        let request_content_type = self.outstanding.remove(&request_index)?;
+        let response = if header.content_type == Nack::CONTENT_TYPE {
+            Response::Nack {
+                request_index,
+                request_content_type,
+            }
+        } else {
+            Response::Reply {
+                request_index,
+                request_content_type,
+            }
+        };
+        Some(response)
+    }
+
+    /// Stop tracking the outstanding request with the given index, e.g. after a timeout.
+    ///
+    /// Returns the content type of the request if it was outstanding.
+    pub fn cancel(&mut self, request_index: u16) -> Option<Cookie> {
+        self.outstanding.remove(&request_index)
+    }
+
+    /// The number of outstanding requests.
+    pub fn len(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    /// Whether or not there are no outstanding requests.
+    pub fn is_empty(&self) -> bool {
+        self.outstanding.is_empty()
+    }
+}
+
+impl<'a> GELI<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"GELI";
+}
+
+impl<'a> ELIn<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"ELIn";
+}
+
+impl ELUp {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"ELUp";
+
+    /// Existing elements have been updated.
+    pub const EXISTING_ELEMENTS_UPDATED: u8 = 0x01;
+    /// Elements have been added or removed.
+    pub const ELEMENTS_ADDED_OR_REMOVED: u8 = 0x02;
+    /// MSEX 1.1 - Sub libraries have been updated.
+    pub const SUB_LIBRARIES_UPDATED: u8 = 0x04;
+    /// MSEX 1.1 - Sub libraries have been added or removed.
+    pub const SUB_LIBRARIES_ADDED_OR_REMOVED: u8 = 0x08;
+    /// MSEX 1.2 - All elements are affected, `affected_elements` may be ignored.
+    pub const ALL_ELEMENTS_AFFECTED: u8 = 0x10;
+    /// MSEX 1.2 - All sub libraries are affected, `affected_libraries` may be ignored.
+    pub const ALL_LIBRARIES_AFFECTED: u8 = 0x20;
+
+    /// Whether or not the element with the given number is affected by the update.
+    pub fn is_element_affected(&self, element_number: u8) -> bool {
+        self.update_flags & Self::ALL_ELEMENTS_AFFECTED != 0
+            || bit_array_contains(&self.affected_elements, element_number)
+    }
+
+    /// Whether or not the sub-library with the given number is affected by the update.
+    pub fn is_library_affected(&self, library_number: u8) -> bool {
+        self.update_flags & Self::ALL_LIBRARIES_AFFECTED != 0
+            || bit_array_contains(&self.affected_libraries, library_number)
+    }
+}
+
+impl<'a> GEIn<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"GEIn";
+}
+
+impl<'a> MEIn<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"MEIn";
+}
+
+impl<'a> EEIn<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"EEIn";
+}
+
+impl<'a> GLEI<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"GLEI";
+}
+
+impl<'a> GELT<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"GELT";
+
+    /// Preserve the aspect ratio of the thumbnail when scaling it to the requested size.
+    pub const PRESERVE_ASPECT_RATIO: u8 = 0x01;
+
+    /// Whether or not the `PRESERVE_ASPECT_RATIO` flag is set.
+    pub fn preserve_aspect_ratio(&self) -> bool {
+        self.thumbnail_flags & Self::PRESERVE_ASPECT_RATIO != 0
+    }
+}
+
+impl<'a> ELTh<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"ELTh";
+}
+
+impl<'a> GETh<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"GETh";
+
+    /// Preserve the aspect ratio of the thumbnail when scaling it to the requested size.
+    pub const PRESERVE_ASPECT_RATIO: u8 = 0x01;
+
+    /// Whether or not the `PRESERVE_ASPECT_RATIO` flag is set.
+    pub fn preserve_aspect_ratio(&self) -> bool {
+        self.thumbnail_flags & Self::PRESERVE_ASPECT_RATIO != 0
+    }
+}
+
+impl<'a> EThn<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"EThn";
+}
+
+impl GVSr {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"GVSr";
+}
+
+impl<'a> VSrc<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"VSrc";
+}
+
+impl SourceInformation {
+    /// The source is provided without any effects applied.
+    pub const WITHOUT_EFFECTS: u16 = 0x0001;
+}
+
+impl RqSt {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"RqSt";
+}
+
+impl<'a> StFr<'a> {
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"StFr";
+}
+
+/// Whether or not the given frame format cookie describes a fragmented frame format.
+pub fn is_fragmented_format(format: Cookie) -> bool {
+    format == IMAGE_FORMAT_FRAGMENTED_JPEG || format == IMAGE_FORMAT_FRAGMENTED_PNG
+}
+
+impl LayerStatus {
+    /// Media is playing.
+    pub const MEDIA_PLAYING: u32 = 0x0001;
+    /// MSEX 1.2 - Media playback is reversed.
+    pub const MEDIA_PLAYBACK_REVERSE: u32 = 0x0002;
+    /// MSEX 1.2 - Media playback is looping.
+    pub const MEDIA_PLAYBACK_LOOPING: u32 = 0x0004;
+    /// MSEX 1.2 - Media playback is bouncing.
+    pub const MEDIA_PLAYBACK_BOUNCING: u32 = 0x0008;
+    /// MSEX 1.2 - Media playback is random.
+    pub const MEDIA_PLAYBACK_RANDOM: u32 = 0x0010;
+    /// MSEX 1.2 - Media is paused.
+    pub const MEDIA_PAUSED: u32 = 0x0020;
+
+    /// Whether or not the media is playing.
+    pub fn is_playing(&self) -> bool {
+        self.layer_status_flags & Self::MEDIA_PLAYING != 0
+    }
+
+    /// Whether or not the media playback is reversed.
+    pub fn is_reverse(&self) -> bool {
+        self.layer_status_flags & Self::MEDIA_PLAYBACK_REVERSE != 0
+    }
+
+    /// Whether or not the media playback is looping.
+    pub fn is_looping(&self) -> bool {
+        self.layer_status_flags & Self::MEDIA_PLAYBACK_LOOPING != 0
+    }
+
+    /// Whether or not the media playback is bouncing.
+    pub fn is_bouncing(&self) -> bool {
+        self.layer_status_flags & Self::MEDIA_PLAYBACK_BOUNCING != 0
+    }
+
+    /// Whether or not the media playback is random.
+    pub fn is_random(&self) -> bool {
+        self.layer_status_flags & Self::MEDIA_PLAYBACK_RANDOM != 0
+    }
+
+    /// Whether or not the media is paused.
+    pub fn is_paused(&self) -> bool {
+        self.layer_status_flags & Self::MEDIA_PAUSED != 0
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl<'a> ContentType for CInf<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = CInf::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for SInf<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = SInf::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for LSta<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = LSta::CONTENT_TYPE;
+}
+
+impl ContentType for Nack {
+    const CONTENT_TYPE: &'static [u8; 4] = Nack::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for GELI<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = GELI::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for ELIn<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = ELIn::CONTENT_TYPE;
+}
+
+impl ContentType for ELUp {
+    const CONTENT_TYPE: &'static [u8; 4] = ELUp::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for GEIn<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = GEIn::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for MEIn<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = MEIn::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for EEIn<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = EEIn::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for GLEI<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = GLEI::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for GELT<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = GELT::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for ELTh<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = ELTh::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for GETh<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = GETh::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for EThn<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = EThn::CONTENT_TYPE;
+}
+
+impl ContentType for GVSr {
+    const CONTENT_TYPE: &'static [u8; 4] = GVSr::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for VSrc<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = VSrc::CONTENT_TYPE;
+}
+
+impl ContentType for RqSt {
+    const CONTENT_TYPE: &'static [u8; 4] = RqSt::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for StFr<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = StFr::CONTENT_TYPE;
+}
+
+impl<T> Message<T>
+    where
+        T: ContentType + SizeBytesVersioned,
+{
+    /// Create a new MSEX message of the given version, filling in the cookies of all headers and
+    /// the `message_size` of the CITP header.
+    ///
+    /// The request index is `0`, i.e. "ignored". See `Message::request` and `Message::response`
+    /// for correlating requests and responses.
+    pub fn new(version: Version, message: T) -> Self {
+        Self::with_kind(version, message, protocol::Kind::default())
+    }
+
+    /// Create a new MSEX request, registering it with the given set of outstanding `requests` and
+    /// filling in the resulting request index.
+    pub fn request(version: Version, message: T, requests: &mut Requests) -> Self {
+        let kind = requests.request(Cookie::new(T::CONTENT_TYPE));
+        Self::with_kind(version, message, kind)
+    }
+
+    /// Create a new MSEX response to the request with the given header, filling in the request
+    /// index of the request.
+    pub fn response(version: Version, message: T, request: &Header) -> Self {
+        let kind = request.citp_header.kind.into_response();
+        Self::with_kind(version, message, kind)
+    }
+
+    fn with_kind(version: Version, message: T, kind: protocol::Kind) -> Self {
+        let citp_content_type = Cookie::new(Header::CONTENT_TYPE);
+        let msex_header = Header {
+            citp_header: protocol::Header::new(citp_content_type, kind, 0),
+            version_major: version.major,
+            version_minor: version.minor,
+            content_type: Cookie::new(T::CONTENT_TYPE),
+        };
+        let mut msg = Message {
+            msex_header,
+            message,
+        };
+        msg.msex_header.citp_header.message_size = msg.size_bytes() as u32;
+        msg
+    }
+}
+
+impl WriteToBytes for Header {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        self.check_kind()?;
+        writer.write_bytes(self.citp_header)?;
+        writer.write_u8(self.version_major)?;
+        writer.write_u8(self.version_minor)?;
+        writer.write_bytes(self.content_type)?;
+        Ok(())
+    }
+}
+
+impl<T> WriteToBytes for Message<T>
+    where
+        T: WriteToBytesVersioned,
+{
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_bytes(self.msex_header)?;
+        self.message.write_to_bytes_versioned(writer, self.msex_header.version())?;
+        Ok(())
+    }
+}
+
+impl WriteToBytes for Version {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        let version = ((self.major as u16) << 8) | self.minor as u16;
+        writer.write_u16::<LE>(version)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytes for LibraryId {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u8(self.level)?;
+        writer.write_u8(self.sub_level_1)?;
+        writer.write_u8(self.sub_level_2)?;
+        writer.write_u8(self.sub_level_3)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytes for Nack {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_bytes(self.received_content_type)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytes for GVSr {
+    fn write_to_bytes<W: WriteBytesExt>(&self, _: W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytes for VSrc<'a> {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        write_count_u16(&mut writer, self.sources.len())?;
+        for source in self.sources.iter() {
+            writer.write_bytes(source)?;
+        }
+        Ok(())
+    }
+}
+
+impl WriteToBytes for SourceInformation {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u16::<LE>(self.source_identifier)?;
+        writer.write_bytes(&self.source_name)?;
+        writer.write_u8(self.physical_output)?;
+        writer.write_u8(self.layer_number)?;
+        writer.write_u16::<LE>(self.flags)?;
+        writer.write_u16::<LE>(self.width)?;
+        writer.write_u16::<LE>(self.height)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytes for RqSt {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u16::<LE>(self.source_identifier)?;
+        writer.write_bytes(self.frame_format)?;
+        writer.write_u16::<LE>(self.frame_width)?;
+        writer.write_u16::<LE>(self.frame_height)?;
+        writer.write_u8(self.fps)?;
+        writer.write_u8(self.timeout)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytes for Fragment {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<LE>(self.frame_index)?;
+        writer.write_u16::<LE>(self.fragment_count)?;
+        writer.write_u16::<LE>(self.fragment_index)?;
+        writer.write_u32::<LE>(self.fragment_byte_offset)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytes for CInf<'a> {
+    fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
+        write_count_u8(&mut writer, self.supported_msex_versions.len())?;
+        for version in self.supported_msex_versions.iter() {
+            writer.write_bytes(version)?;
+        }
+        writer.write_all(&self.future_message_data)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for CInf<'a> {
+    fn write_to_bytes_versioned<W: WriteBytesExt>(&self, writer: W, _: Version) -> io::Result<()> {
+        self.write_to_bytes(writer)
+    }
+}
+
+impl<'a> WriteToBytesVersioned for SInf<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        match Layout::of(version)? {
+            Layout::V1_0 | Layout::V1_1 => {
+                writer.write_bytes(&self.product_name)?;
+                writer.write_u8(self.product_version_major)?;
+                writer.write_u8(self.product_version_minor)?;
+            }
+            Layout::V1_2 => {
+                writer.write_all(&self.uuid)?;
+                writer.write_bytes(&self.product_name)?;
+                writer.write_u8(self.product_version_major)?;
+                writer.write_u8(self.product_version_minor)?;
+                writer.write_u8(self.product_version_bugfix)?;
+                write_count_u8(&mut writer, self.supported_msex_versions.len())?;
+                for version in self.supported_msex_versions.iter() {
+                    writer.write_bytes(version)?;
+                }
+                writer.write_u16::<LE>(self.supported_library_types)?;
+                write_count_u8(&mut writer, self.thumbnail_formats.len())?;
+                for format in self.thumbnail_formats.iter() {
+                    writer.write_bytes(format)?;
+                }
+                write_count_u8(&mut writer, self.stream_formats.len())?;
+                for format in self.stream_formats.iter() {
+                    writer.write_bytes(format)?;
+                }
+            }
+        }
+        write_count_u8(&mut writer, self.layer_dmx_sources.len())?;
+        for dmx_source in self.layer_dmx_sources.iter() {
+            writer.write_bytes(dmx_source)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for LSta<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        write_count_u8(&mut writer, self.layer_statuses.len())?;
+        for layer_status in self.layer_statuses.iter() {
+            layer_status.write_to_bytes_versioned(&mut writer, version)?;
+        }
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for LayerStatus {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.layer_number)?;
+        writer.write_u8(self.physical_output)?;
+        match Layout::of(version)? {
+            Layout::V1_0 | Layout::V1_1 => {
+                writer.write_u8(self.media_library_number)?;
+            }
+            Layout::V1_2 => {
+                writer.write_u8(self.media_library_type)?;
+                writer.write_bytes(self.media_library_id)?;
+            }
+        }
+        writer.write_u8(self.media_number)?;
+        writer.write_bytes(&self.media_name)?;
+        writer.write_u32::<LE>(self.media_position)?;
+        writer.write_u32::<LE>(self.media_length)?;
+        writer.write_u8(self.media_fps)?;
+        writer.write_u32::<LE>(self.layer_status_flags)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for Nack {
+    fn write_to_bytes_versioned<W>(&self, writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        match Layout::of(version)? {
+            Layout::V1_2 => self.write_to_bytes(writer),
+            _ => Err(unsupported_version(version)),
+        }
+    }
+}
+
+impl<'a> WriteToBytesVersioned for GELI<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.library_type)?;
+        match Layout::of(version)? {
+            Layout::V1_0 => (),
+            Layout::V1_1 | Layout::V1_2 => writer.write_bytes(self.library_parent_id)?,
+        }
+        write_count_versioned(&mut writer, self.library_numbers.len(), version)?;
+        writer.write_all(&self.library_numbers)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for ELIn<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.library_type)?;
+        write_count_versioned(&mut writer, self.elements.len(), version)?;
+        for element in self.elements.iter() {
+            element.write_to_bytes_versioned(&mut writer, version)?;
+        }
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for LibraryInformation {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        let layout = Layout::of(version)?;
+        match layout {
+            Layout::V1_0 => writer.write_u8(self.number)?,
+            Layout::V1_1 => writer.write_bytes(self.id)?,
+            Layout::V1_2 => {
+                writer.write_bytes(self.id)?;
+                writer.write_u32::<LE>(self.serial_number)?;
+            }
+        }
+        writer.write_u8(self.dmx_range_min)?;
+        writer.write_u8(self.dmx_range_max)?;
+        writer.write_bytes(&self.name)?;
+        if layout != Layout::V1_0 {
+            write_count_versioned(&mut writer, self.library_count as _, version)?;
+        }
+        write_count_versioned(&mut writer, self.element_count as _, version)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for ELUp {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.library_type)?;
+        write_library(&mut writer, self.library_number, self.library_id, version)?;
+        writer.write_u8(self.update_flags)?;
+        if Layout::of(version)? == Layout::V1_2 {
+            writer.write_all(&self.affected_elements)?;
+            writer.write_all(&self.affected_libraries)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for GEIn<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.library_type)?;
+        write_library(&mut writer, self.library_number, self.library_id, version)?;
+        write_count_versioned(&mut writer, self.element_numbers.len(), version)?;
+        writer.write_all(&self.element_numbers)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for MEIn<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        write_library(&mut writer, self.library_number, self.library_id, version)?;
+        write_count_versioned(&mut writer, self.elements.len(), version)?;
+        for element in self.elements.iter() {
+            element.write_to_bytes_versioned(&mut writer, version)?;
+        }
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for MediaInformation {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.number)?;
+        if Layout::of(version)? == Layout::V1_2 {
+            writer.write_u32::<LE>(self.serial_number)?;
+        }
+        writer.write_u8(self.dmx_range_min)?;
+        writer.write_u8(self.dmx_range_max)?;
+        writer.write_bytes(&self.media_name)?;
+        writer.write_u64::<LE>(self.media_version_timestamp)?;
+        writer.write_u16::<LE>(self.media_width)?;
+        writer.write_u16::<LE>(self.media_height)?;
+        writer.write_u32::<LE>(self.media_length)?;
+        writer.write_u8(self.media_fps)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for EEIn<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        write_library(&mut writer, self.library_number, self.library_id, version)?;
+        write_count_versioned(&mut writer, self.elements.len(), version)?;
+        for element in self.elements.iter() {
+            element.write_to_bytes_versioned(&mut writer, version)?;
+        }
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for EffectInformation {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.number)?;
+        if Layout::of(version)? == Layout::V1_2 {
+            writer.write_u32::<LE>(self.serial_number)?;
+        }
+        writer.write_u8(self.dmx_range_min)?;
+        writer.write_u8(self.dmx_range_max)?;
+        writer.write_bytes(&self.effect_name)?;
+        write_count_u8(&mut writer, self.effect_parameter_names.len())?;
+        for name in &self.effect_parameter_names {
+            writer.write_bytes(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for GLEI<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        match Layout::of(version)? {
+            Layout::V1_0 => return Err(unsupported_version(version)),
+            Layout::V1_1 | Layout::V1_2 => (),
+        }
+        writer.write_bytes(self.library_id)?;
+        writer.write_u8(self.library_type)?;
+        write_count_versioned(&mut writer, self.elements.len(), version)?;
+        for element in self.elements.iter() {
+            element.write_to_bytes_versioned(&mut writer, version)?;
+        }
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for GenericInformation {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.number)?;
+        if Layout::of(version)? == Layout::V1_2 {
+            writer.write_u32::<LE>(self.serial_number)?;
+        }
+        writer.write_u8(self.dmx_range_min)?;
+        writer.write_u8(self.dmx_range_max)?;
+        writer.write_bytes(&self.name)?;
+        writer.write_u64::<LE>(self.version_timestamp)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for GELT<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_bytes(self.thumbnail_format)?;
+        writer.write_u16::<LE>(self.thumbnail_width)?;
+        writer.write_u16::<LE>(self.thumbnail_height)?;
+        writer.write_u8(self.thumbnail_flags)?;
+        writer.write_u8(self.library_type)?;
+        match Layout::of(version)? {
+            Layout::V1_0 => {
+                write_count_versioned(&mut writer, self.library_numbers.len(), version)?;
+                writer.write_all(&self.library_numbers)?;
+            }
+            Layout::V1_1 | Layout::V1_2 => {
+                write_count_versioned(&mut writer, self.library_ids.len(), version)?;
+                for id in self.library_ids.iter() {
+                    writer.write_bytes(id)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for ELTh<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.library_type)?;
+        write_library(&mut writer, self.library_number, self.library_id, version)?;
+        writer.write_bytes(self.thumbnail_format)?;
+        writer.write_u16::<LE>(self.thumbnail_width)?;
+        writer.write_u16::<LE>(self.thumbnail_height)?;
+        write_count_u16(&mut writer, self.thumbnail_buffer.len())?;
+        writer.write_all(&self.thumbnail_buffer)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for GETh<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_bytes(self.thumbnail_format)?;
+        writer.write_u16::<LE>(self.thumbnail_width)?;
+        writer.write_u16::<LE>(self.thumbnail_height)?;
+        writer.write_u8(self.thumbnail_flags)?;
+        writer.write_u8(self.library_type)?;
+        write_library(&mut writer, self.library_number, self.library_id, version)?;
+        write_count_versioned(&mut writer, self.element_numbers.len(), version)?;
+        writer.write_all(&self.element_numbers)?;
+        Ok(())
+    }
+}
+
+impl<'a> WriteToBytesVersioned for EThn<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        writer.write_u8(self.library_type)?;
+        write_library(&mut writer, self.library_number, self.library_id, version)?;
+        writer.write_u8(self.element_number)?;
+        writer.write_bytes(self.thumbnail_format)?;
+        writer.write_u16::<LE>(self.thumbnail_width)?;
+        writer.write_u16::<LE>(self.thumbnail_height)?;
+        write_count_u16(&mut writer, self.thumbnail_buffer.len())?;
+        writer.write_all(&self.thumbnail_buffer)?;
+        Ok(())
+    }
+}
+
+impl WriteToBytesVersioned for GVSr {
+    fn write_to_bytes_versioned<W>(&self, writer: W, _: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        self.write_to_bytes(writer)
+    }
+}
+
+impl<'a> WriteToBytesVersioned for VSrc<'a> {
+    fn write_to_bytes_versioned<W>(&self, writer: W, _: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        self.write_to_bytes(writer)
+    }
+}
+
+impl WriteToBytesVersioned for RqSt {
+    fn write_to_bytes_versioned<W>(&self, writer: W, _: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        self.write_to_bytes(writer)
+    }
+}
+
+impl<'a> WriteToBytesVersioned for StFr<'a> {
+    fn write_to_bytes_versioned<W>(&self, mut writer: W, version: Version) -> io::Result<()>
+        where
+            W: WriteBytesExt,
+    {
+        let layout = Layout::of(version)?;
+        match layout {
+            Layout::V1_0 | Layout::V1_1 => (),
+            Layout::V1_2 => writer.write_all(&self.media_server_uuid)?,
+        }
+        writer.write_u16::<LE>(self.source_identifier)?;
+        writer.write_bytes(self.frame_format)?;
+        writer.write_u16::<LE>(self.frame_width)?;
+        writer.write_u16::<LE>(self.frame_height)?;
+        let fragmented = layout == Layout::V1_2 && is_fragmented_format(self.frame_format);
+        match self.fragment {
+            Some(ref fragment) if fragmented => {
+                let frame_buffer_size = fragment.size_bytes() + self.frame_buffer.len();
+                write_count_u16(&mut writer, frame_buffer_size)?;
+                writer.write_bytes(fragment)?;
+            }
+            None if !fragmented => {
+                write_count_u16(&mut writer, self.frame_buffer.len())?;
+            }
+            _ => {
+                let err_msg = "the fragment must be `Some` only for fragmented MSEX 1.2 formats";
+                return Err(Error::InvalidMessage(err_msg).into());
+            }
+        }
+        writer.write_all(&self.frame_buffer)?;
+        Ok(())
+    }
+}
+
+impl ReadFromBytes for Header {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let citp_header = reader.read_bytes()?;
+        let version_major = reader.read_u8()?;
+        let version_minor = reader.read_u8()?;
+        let content_type = reader.read_bytes()?;
+        let mut header = Header {
+            citp_header,
+            version_major,
+            version_minor,
+            content_type,
+        };
+        if header.is_response() {
+            header.citp_header.kind = header.citp_header.kind.into_response();
+        }
+        Ok(header)
+    }
+}
+
+/// Reads the header followed by the message.
+///
+/// The reader is limited to the remainder of the message as described by the `message_size` of
+/// the CITP header. Any trailing data not consumed by the message (e.g. fields appended by a later
+/// version of MSEX) is skipped.
+impl<T> ReadFromBytes for Message<T>
+    where
+        T: ContentType + ReadFromBytesVersioned,
+{
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let msex_header: Header = reader.read_bytes()?;
+        protocol::check_cookie(msex_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(msex_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(msex_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = msex_header.citp_header.message_size;
+        let headers_size = msex_header.size_bytes();
+        let version = msex_header.version();
+        let message = limits::read_message_body(reader, message_size, headers_size, |r| {
+            T::read_from_bytes_versioned(r, version)
+        })?;
+        let msg = Message {
+            msex_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
+impl ReadFromBytes for Version {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let version = reader.read_u16::<LE>()?;
+        let major = (version >> 8) as u8;
+        let minor = version as u8;
+        Ok(Version { major, minor })
+    }
+}
+
+impl ReadFromBytes for LibraryId {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let level = reader.read_u8()?;
+        let sub_level_1 = reader.read_u8()?;
+        let sub_level_2 = reader.read_u8()?;
+        let sub_level_3 = reader.read_u8()?;
+        let id = LibraryId {
+            level,
+            sub_level_1,
+            sub_level_2,
+            sub_level_3,
+        };
+        Ok(id)
+    }
+}
+
+impl ReadFromBytes for Nack {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let received_content_type = reader.read_bytes()?;
+        let nack = Nack {
+            received_content_type,
+        };
+        Ok(nack)
+    }
+}
+
+impl ReadFromBytes for GVSr {
+    fn read_from_bytes<R: ReadBytesExt>(_: R) -> io::Result<Self> {
+        Ok(GVSr)
+    }
+}
+
+impl ReadFromBytes for VSrc<'static> {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let source_count = reader.read_u16::<LE>()?;
+        let sources = protocol::read_new_vec(&mut reader, source_count as _)?;
+        let vsrc = VSrc {
+            sources: Cow::Owned(sources),
+        };
+        Ok(vsrc)
+    }
+}
+
+impl ReadFromBytes for SourceInformation {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let source_identifier = reader.read_u16::<LE>()?;
+        let source_name = reader.read_bytes()?;
+        let physical_output = reader.read_u8()?;
+        let layer_number = reader.read_u8()?;
+        let flags = reader.read_u16::<LE>()?;
+        let width = reader.read_u16::<LE>()?;
+        let height = reader.read_u16::<LE>()?;
+        let source_information = SourceInformation {
+            source_identifier,
+            source_name,
+            physical_output,
+            layer_number,
+            flags,
+            width,
+            height,
+        };
+        Ok(source_information)
+    }
+}
+
+impl ReadFromBytes for RqSt {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let source_identifier = reader.read_u16::<LE>()?;
+        let frame_format = reader.read_bytes()?;
+        let frame_width = reader.read_u16::<LE>()?;
+        let frame_height = reader.read_u16::<LE>()?;
+        let fps = reader.read_u8()?;
+        let timeout = reader.read_u8()?;
+        let rqst = RqSt {
+            source_identifier,
+            frame_format,
+            frame_width,
+            frame_height,
+            fps,
+            timeout,
+        };
+        Ok(rqst)
+    }
+}
+
+impl ReadFromBytes for Fragment {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let frame_index = reader.read_u32::<LE>()?;
+        let fragment_count = reader.read_u16::<LE>()?;
+        let fragment_index = reader.read_u16::<LE>()?;
+        let fragment_byte_offset = reader.read_u32::<LE>()?;
+        let fragment = Fragment {
+            frame_index,
+            fragment_count,
+            fragment_index,
+            fragment_byte_offset,
+        };
+        Ok(fragment)
+    }
+}
+
+/// Reads the supported versions followed by all remaining bytes as the `future_message_data`.
+///
+/// As a result, the reader should be limited to the size of the message. Reading a full
+/// `Message<CInf>` takes care of this using the `message_size` of the CITP header.
+impl ReadFromBytes for CInf<'static> {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let supported_msex_versions_count = reader.read_u8()?;
+        let supported_msex_versions =
+            protocol::read_new_vec(&mut reader, supported_msex_versions_count as _)?;
+        let mut future_message_data = vec![];
+        reader.read_to_end(&mut future_message_data)?;
+        let cinf = CInf {
+            supported_msex_versions: Cow::Owned(supported_msex_versions),
+            future_message_data: Cow::Owned(future_message_data),
+        };
+        Ok(cinf)
+    }
+}
+
+impl ReadFromBytesVersioned for CInf<'static> {
+    fn read_from_bytes_versioned<R: ReadBytesExt>(reader: R, _: Version) -> io::Result<Self> {
+        Self::read_from_bytes(reader)
+    }
+}
+
+impl ReadFromBytesVersioned for SInf<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let mut sinf = SInf {
+            uuid: [0; 36],
+            product_name: Ucs2String::default(),
+            product_version_major: 0,
+            product_version_minor: 0,
+            product_version_bugfix: 0,
+            supported_msex_versions: Cow::Owned(vec![]),
+            supported_library_types: 0,
+            thumbnail_formats: Cow::Owned(vec![]),
+            stream_formats: Cow::Owned(vec![]),
+            layer_dmx_sources: Cow::Owned(vec![]),
+        };
+        match Layout::of(version)? {
+            Layout::V1_0 | Layout::V1_1 => {
+                sinf.product_name = reader.read_bytes()?;
+                sinf.product_version_major = reader.read_u8()?;
+                sinf.product_version_minor = reader.read_u8()?;
+            }
+            Layout::V1_2 => {
+                reader.read_exact(&mut sinf.uuid)?;
+                sinf.product_name = reader.read_bytes()?;
+                sinf.product_version_major = reader.read_u8()?;
+                sinf.product_version_minor = reader.read_u8()?;
+                sinf.product_version_bugfix = reader.read_u8()?;
+                let supported_msex_versions_count = reader.read_u8()?;
+                let supported_msex_versions =
+                    protocol::read_new_vec(&mut reader, supported_msex_versions_count as _)?;
+                sinf.supported_msex_versions = Cow::Owned(supported_msex_versions);
+                sinf.supported_library_types = reader.read_u16::<LE>()?;
+                let thumbnail_format_count = reader.read_u8()?;
+                let thumbnail_formats =
+                    protocol::read_new_vec(&mut reader, thumbnail_format_count as _)?;
+                sinf.thumbnail_formats = Cow::Owned(thumbnail_formats);
+                let stream_format_count = reader.read_u8()?;
+                let stream_formats = protocol::read_new_vec(&mut reader, stream_format_count as _)?;
+                sinf.stream_formats = Cow::Owned(stream_formats);
+            }
+        }
+        let layer_count = reader.read_u8()?;
+        let layer_dmx_sources = protocol::read_new_vec(&mut reader, layer_count as _)?;
+        sinf.layer_dmx_sources = Cow::Owned(layer_dmx_sources);
+        Ok(sinf)
+    }
+}
+
+impl ReadFromBytesVersioned for LSta<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let layer_count = reader.read_u8()?;
+        let layer_statuses = read_new_vec_versioned(&mut reader, layer_count as _, version)?;
+        let lsta = LSta {
+            layer_statuses: Cow::Owned(layer_statuses),
+        };
+        Ok(lsta)
+    }
+}
+
+impl ReadFromBytesVersioned for LayerStatus {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let layer_number = reader.read_u8()?;
+        let physical_output = reader.read_u8()?;
+        let mut media_library_number = 0;
+        let mut media_library_type = 0;
+        let mut media_library_id = LibraryId::default();
+        match Layout::of(version)? {
+            Layout::V1_0 | Layout::V1_1 => {
+                media_library_number = reader.read_u8()?;
+            }
+            Layout::V1_2 => {
+                media_library_type = reader.read_u8()?;
+                media_library_id = reader.read_bytes()?;
+            }
+        }
+        let media_number = reader.read_u8()?;
+        let media_name = reader.read_bytes()?;
+        let media_position = reader.read_u32::<LE>()?;
+        let media_length = reader.read_u32::<LE>()?;
+        let media_fps = reader.read_u8()?;
+        let layer_status_flags = reader.read_u32::<LE>()?;
+        let layer_status = LayerStatus {
+            layer_number,
+            physical_output,
+            media_library_number,
+            media_library_type,
+            media_library_id,
+            media_number,
+            media_name,
+            media_position,
+            media_length,
+            media_fps,
+            layer_status_flags,
+        };
+        Ok(layer_status)
+    }
+}
+
+impl ReadFromBytesVersioned for Nack {
+    fn read_from_bytes_versioned<R>(reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        match Layout::of(version)? {
+            Layout::V1_2 => Self::read_from_bytes(reader),
+            _ => Err(unsupported_version(version)),
+        }
+    }
+}
+
+impl ReadFromBytesVersioned for GELI<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let library_type = reader.read_u8()?;
+        let library_parent_id = match Layout::of(version)? {
+            Layout::V1_0 => LibraryId::default(),
+            Layout::V1_1 | Layout::V1_2 => reader.read_bytes()?,
+        };
+        let library_count = read_count_versioned(&mut reader, version)?;
+        let library_numbers = protocol::read_new_vec(&mut reader, library_count)?;
+        let geli = GELI {
+            library_type,
+            library_parent_id,
+            library_numbers: Cow::Owned(library_numbers),
+        };
+        Ok(geli)
+    }
+}
+
+impl ReadFromBytesVersioned for ELIn<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let library_type = reader.read_u8()?;
+        let library_count = read_count_versioned(&mut reader, version)?;
+        let elements = read_new_vec_versioned(&mut reader, library_count, version)?;
+        let elin = ELIn {
+            library_type,
+            elements: Cow::Owned(elements),
+        };
+        Ok(elin)
+    }
+}
+
+impl ReadFromBytesVersioned for LibraryInformation {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let mut number = 0;
+        let mut id = LibraryId::default();
+        let mut serial_number = 0;
+        let layout = Layout::of(version)?;
+        match layout {
+            Layout::V1_0 => number = reader.read_u8()?,
+            Layout::V1_1 => id = reader.read_bytes()?,
+            Layout::V1_2 => {
+                id = reader.read_bytes()?;
+                serial_number = reader.read_u32::<LE>()?;
+            }
+        }
+        let dmx_range_min = reader.read_u8()?;
+        let dmx_range_max = reader.read_u8()?;
+        let name = reader.read_bytes()?;
+        let library_count = match layout {
+            Layout::V1_0 => 0,
+            Layout::V1_1 | Layout::V1_2 => read_count_versioned(&mut reader, version)? as u16,
+        };
+        let element_count = read_count_versioned(&mut reader, version)? as u16;
+        let library_information = LibraryInformation {
+            number,
+            id,
+            serial_number,
+            dmx_range_min,
+            dmx_range_max,
+            name,
+            library_count,
+            element_count,
+        };
+        Ok(library_information)
+    }
+}
+
+impl ReadFromBytesVersioned for ELUp {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let library_type = reader.read_u8()?;
+        let (library_number, library_id) = read_library(&mut reader, version)?;
+        let update_flags = reader.read_u8()?;
+        let mut affected_elements = [0; 32];
+        let mut affected_libraries = [0; 32];
+        if Layout::of(version)? == Layout::V1_2 {
+            reader.read_exact(&mut affected_elements)?;
+            reader.read_exact(&mut affected_libraries)?;
+        }
+        let elup = ELUp {
+            library_type,
+            library_number,
+            library_id,
+            update_flags,
+            affected_elements,
+            affected_libraries,
+        };
+        Ok(elup)
+    }
+}
+
+impl ReadFromBytesVersioned for GEIn<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let library_type = reader.read_u8()?;
+        let (library_number, library_id) = read_library(&mut reader, version)?;
+        let element_count = read_count_versioned(&mut reader, version)?;
+        let element_numbers = protocol::read_new_vec(&mut reader, element_count)?;
+        let gein = GEIn {
+            library_type,
+            library_number,
+            library_id,
+            element_numbers: Cow::Owned(element_numbers),
+        };
+        Ok(gein)
+    }
+}
+
+impl ReadFromBytesVersioned for MEIn<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let (library_number, library_id) = read_library(&mut reader, version)?;
+        let element_count = read_count_versioned(&mut reader, version)?;
+        let elements = read_new_vec_versioned(&mut reader, element_count, version)?;
+        let mein = MEIn {
+            library_number,
+            library_id,
+            elements: Cow::Owned(elements),
+        };
+        Ok(mein)
+    }
+}
+
+impl ReadFromBytesVersioned for MediaInformation {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let number = reader.read_u8()?;
+        let serial_number = match Layout::of(version)? {
+            Layout::V1_0 | Layout::V1_1 => 0,
+            Layout::V1_2 => reader.read_u32::<LE>()?,
+        };
+        let dmx_range_min = reader.read_u8()?;
+        let dmx_range_max = reader.read_u8()?;
+        let media_name = reader.read_bytes()?;
+        let media_version_timestamp = reader.read_u64::<LE>()?;
+        let media_width = reader.read_u16::<LE>()?;
+        let media_height = reader.read_u16::<LE>()?;
+        let media_length = reader.read_u32::<LE>()?;
+        let media_fps = reader.read_u8()?;
+        let media_information = MediaInformation {
+            number,
+            serial_number,
+            dmx_range_min,
+            dmx_range_max,
+            media_name,
+            media_version_timestamp,
+            media_width,
+            media_height,
+            media_length,
+            media_fps,
+        };
+        Ok(media_information)
+    }
+}
+
+impl ReadFromBytesVersioned for EEIn<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let (library_number, library_id) = read_library(&mut reader, version)?;
+        let element_count = read_count_versioned(&mut reader, version)?;
+        let elements = read_new_vec_versioned(&mut reader, element_count, version)?;
+        let eein = EEIn {
+            library_number,
+            library_id,
+            elements: Cow::Owned(elements),
+        };
+        Ok(eein)
+    }
+}
+
+impl ReadFromBytesVersioned for EffectInformation {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let number = reader.read_u8()?;
+        let serial_number = match Layout::of(version)? {
+            Layout::V1_0 | Layout::V1_1 => 0,
+            Layout::V1_2 => reader.read_u32::<LE>()?,
+        };
+        let dmx_range_min = reader.read_u8()?;
+        let dmx_range_max = reader.read_u8()?;
+        let effect_name = reader.read_bytes()?;
+        let effect_parameter_count = reader.read_u8()?;
+        Limits::current().check_list_len(effect_parameter_count as usize)?;
+        let mut effect_parameter_names = Vec::with_capacity(effect_parameter_count as usize);
+        for _ in 0..effect_parameter_count {
+            effect_parameter_names.push(reader.read_bytes()?);
+        }
+        let effect_information = EffectInformation {
+            number,
+            serial_number,
+            dmx_range_min,
+            dmx_range_max,
+            effect_name,
+            effect_parameter_names,
+        };
+        Ok(effect_information)
+    }
+}
+
+impl ReadFromBytesVersioned for GLEI<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        match Layout::of(version)? {
+            Layout::V1_0 => return Err(unsupported_version(version)),
+            Layout::V1_1 | Layout::V1_2 => (),
+        }
+        let library_id = reader.read_bytes()?;
+        let library_type = reader.read_u8()?;
+        let element_count = read_count_versioned(&mut reader, version)?;
+        let elements = read_new_vec_versioned(&mut reader, element_count, version)?;
+        let glei = GLEI {
+            library_id,
+            library_type,
+            elements: Cow::Owned(elements),
+        };
+        Ok(glei)
+    }
+}
+
+impl ReadFromBytesVersioned for GenericInformation {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let number = reader.read_u8()?;
+        let serial_number = match Layout::of(version)? {
+            Layout::V1_0 | Layout::V1_1 => 0,
+            Layout::V1_2 => reader.read_u32::<LE>()?,
+        };
+        let dmx_range_min = reader.read_u8()?;
+        let dmx_range_max = reader.read_u8()?;
+        let name = reader.read_bytes()?;
+        let version_timestamp = reader.read_u64::<LE>()?;
+        let generic_information = GenericInformation {
+            number,
+            serial_number,
+            dmx_range_min,
+            dmx_range_max,
+            name,
+            version_timestamp,
+        };
+        Ok(generic_information)
+    }
+}
+
+impl ReadFromBytesVersioned for GELT<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let thumbnail_format = reader.read_bytes()?;
+        let thumbnail_width = reader.read_u16::<LE>()?;
+        let thumbnail_height = reader.read_u16::<LE>()?;
+        let thumbnail_flags = reader.read_u8()?;
+        let library_type = reader.read_u8()?;
+        let mut library_numbers = vec![];
+        let mut library_ids = vec![];
+        match Layout::of(version)? {
+            Layout::V1_0 => {
+                let library_count = read_count_versioned(&mut reader, version)?;
+                library_numbers = protocol::read_new_vec(&mut reader, library_count)?;
+            }
+            Layout::V1_1 | Layout::V1_2 => {
+                let library_count = read_count_versioned(&mut reader, version)?;
+                library_ids = protocol::read_new_vec(&mut reader, library_count)?;
+            }
+        }
+        let gelt = GELT {
+            thumbnail_format,
+            thumbnail_width,
+            thumbnail_height,
+            thumbnail_flags,
+            library_type,
+            library_numbers: Cow::Owned(library_numbers),
+            library_ids: Cow::Owned(library_ids),
+        };
+        Ok(gelt)
+    }
+}
+
+impl ReadFromBytesVersioned for ELTh<'static> {
+    fn read_from_bytes_versioned<R>(reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        read_elth(reader, version, read_owned_buffer)
+    }
+}
+
+impl ReadFromBytesVersioned for GETh<'static> {
+    fn read_from_bytes_versioned<R>(mut reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        let thumbnail_format = reader.read_bytes()?;
+        let thumbnail_width = reader.read_u16::<LE>()?;
+        let thumbnail_height = reader.read_u16::<LE>()?;
+        let thumbnail_flags = reader.read_u8()?;
+        let library_type = reader.read_u8()?;
+        let (library_number, library_id) = read_library(&mut reader, version)?;
+        let element_count = read_count_versioned(&mut reader, version)?;
+        let element_numbers = protocol::read_new_vec(&mut reader, element_count)?;
+        let geth = GETh {
+            thumbnail_format,
+            thumbnail_width,
+            thumbnail_height,
+            thumbnail_flags,
+            library_type,
+            library_number,
+            library_id,
+            element_numbers: Cow::Owned(element_numbers),
+        };
+        Ok(geth)
+    }
+}
+
+impl ReadFromBytesVersioned for EThn<'static> {
+    fn read_from_bytes_versioned<R>(reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        read_ethn(reader, version, read_owned_buffer)
+    }
+}
+
+impl ReadFromBytesVersioned for GVSr {
+    fn read_from_bytes_versioned<R>(reader: R, _: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        Self::read_from_bytes(reader)
+    }
+}
+
+impl ReadFromBytesVersioned for VSrc<'static> {
+    fn read_from_bytes_versioned<R>(reader: R, _: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        Self::read_from_bytes(reader)
+    }
+}
+
+impl ReadFromBytesVersioned for RqSt {
+    fn read_from_bytes_versioned<R>(reader: R, _: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        Self::read_from_bytes(reader)
+    }
+}
+
+impl ReadFromBytesVersioned for StFr<'static> {
+    fn read_from_bytes_versioned<R>(reader: R, version: Version) -> io::Result<Self>
+        where
+            R: ReadBytesExt,
+    {
+        read_stfr(reader, version, read_owned_buffer)
+    }
+}
+
+/// Reads the header followed by the message, borrowing from the slice where the layout of the
+/// message permits.
+///
+/// Behaves the same as **ReadFromBytes**, advancing the slice to the end of the message.
+impl<'a, T> ReadFromSlice<'a> for Message<T>
+    where
+        T: ContentType + ReadFromSliceVersioned<'a>,
+{
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let msex_header: Header = bytes.read_bytes()?;
+        protocol::check_cookie(msex_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(msex_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(msex_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = msex_header.citp_header.message_size;
+        let headers_size = msex_header.size_bytes();
+        let version = msex_header.version();
+        let message = limits::read_message_body_slice(bytes, message_size, headers_size, |b| {
+            T::read_from_slice_versioned(b, version)
+        })?;
+        let msg = Message {
+            msex_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
+impl<'a> ReadFromSliceVersioned<'a> for ELTh<'a> {
+    fn read_from_slice_versioned(bytes: &mut &'a [u8], version: Version) -> io::Result<Self> {
+        read_elth(bytes, version, read_borrowed_buffer)
+    }
+}
+
+impl<'a> ReadFromSliceVersioned<'a> for EThn<'a> {
+    fn read_from_slice_versioned(bytes: &mut &'a [u8], version: Version) -> io::Result<Self> {
+        read_ethn(bytes, version, read_borrowed_buffer)
+    }
+}
+
+impl<'a> ReadFromSliceVersioned<'a> for StFr<'a> {
+    fn read_from_slice_versioned(bytes: &mut &'a [u8], version: Version) -> io::Result<Self> {
+        read_stfr(bytes, version, read_borrowed_buffer)
+    }
+}
+
+impl SizeBytes for Header {
+    fn size_bytes(&self) -> usize {
+        self.citp_header.size_bytes()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u32>()
+    }
+}
+
+impl<T> SizeBytes for Message<T>
+    where
+        T: SizeBytesVersioned,
+{
+    fn size_bytes(&self) -> usize {
+        let version = self.msex_header.version();
+        self.msex_header.size_bytes() + self.message.size_bytes_versioned(version)
+    }
+}
+
+impl SizeBytes for Version {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<u16>()
+    }
+}
+
+impl SizeBytes for LibraryId {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<LibraryId>()
+    }
+}
+
+impl SizeBytes for Nack {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<u32>()
+    }
+}
+
+impl SizeBytes for GVSr {
+    fn size_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl<'a> SizeBytes for VSrc<'a> {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<u16>() + self.sources.iter().map(|s| s.size_bytes()).sum::<usize>()
+    }
+}
+
+impl SizeBytes for SourceInformation {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<u16>()
+            + self.source_name.size_bytes()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+    }
+}
+
+impl SizeBytes for RqSt {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<u16>()
+            + mem::size_of::<u32>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+    }
+}
+
+impl SizeBytes for Fragment {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<Fragment>()
+    }
+}
+
+impl<'a> SizeBytes for CInf<'a> {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<u8>()
+            + self.supported_msex_versions.len() * mem::size_of::<u16>()
+            + self.future_message_data.len()
+    }
+}
+
+impl<'a> SizeBytesVersioned for CInf<'a> {
+    fn size_bytes_versioned(&self, _: Version) -> usize {
+        self.size_bytes()
+    }
+}
+
+impl<'a> SizeBytesVersioned for SInf<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        let size_1_0 = self.product_name.size_bytes()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + self.layer_dmx_sources.iter().map(|s| s.size_bytes()).sum::<usize>();
+        match Layout::of_size(version) {
+            Layout::V1_0 | Layout::V1_1 => size_1_0,
+            Layout::V1_2 => {
+                size_1_0
+                    + self.uuid.len()
+                    + mem::size_of::<u8>()
+                    + mem::size_of::<u8>()
+                    + self.supported_msex_versions.len() * mem::size_of::<u16>()
+                    + mem::size_of::<u16>()
+                    + mem::size_of::<u8>()
+                    + self.thumbnail_formats.len() * mem::size_of::<Cookie>()
+                    + mem::size_of::<u8>()
+                    + self.stream_formats.len() * mem::size_of::<Cookie>()
+            }
+        }
+    }
+}
+
+impl<'a> SizeBytesVersioned for LSta<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + self
+                .layer_statuses
+                .iter()
+                .map(|layer_status| layer_status.size_bytes_versioned(version))
+                .sum::<usize>()
+    }
+}
+
+impl SizeBytesVersioned for LayerStatus {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        let media_library_size = match Layout::of_size(version) {
+            Layout::V1_0 | Layout::V1_1 => mem::size_of::<u8>(),
+            Layout::V1_2 => mem::size_of::<u8>() + self.media_library_id.size_bytes(),
+        };
+        mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + media_library_size
+            + mem::size_of::<u8>()
+            + self.media_name.size_bytes()
+            + mem::size_of::<u32>()
+            + mem::size_of::<u32>()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u32>()
+    }
+}
+
+impl SizeBytesVersioned for Nack {
+    fn size_bytes_versioned(&self, _: Version) -> usize {
+        self.size_bytes()
+    }
+}
+
+impl<'a> SizeBytesVersioned for GELI<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        let library_parent_id_size = match Layout::of_size(version) {
+            Layout::V1_0 => 0,
+            Layout::V1_1 | Layout::V1_2 => self.library_parent_id.size_bytes(),
+        };
+        mem::size_of::<u8>()
+            + library_parent_id_size
+            + count_size_bytes(version)
+            + self.library_numbers.len() * mem::size_of::<u8>()
+    }
+}
+
+impl<'a> SizeBytesVersioned for ELIn<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + count_size_bytes(version)
+            + self
+                .elements
+                .iter()
+                .map(|element| element.size_bytes_versioned(version))
+                .sum::<usize>()
+    }
+}
+
+impl SizeBytesVersioned for LibraryInformation {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        let (id_size, library_count_size) = match Layout::of_size(version) {
+            Layout::V1_0 => (mem::size_of::<u8>(), 0),
+            Layout::V1_1 => (self.id.size_bytes(), count_size_bytes(version)),
+            Layout::V1_2 => (
+                self.id.size_bytes() + mem::size_of::<u32>(),
+                count_size_bytes(version),
+            ),
+        };
+        id_size
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + self.name.size_bytes()
+            + library_count_size
+            + count_size_bytes(version)
+    }
+}
+
+impl SizeBytesVersioned for ELUp {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        let affected_size = match Layout::of_size(version) {
+            Layout::V1_0 | Layout::V1_1 => 0,
+            Layout::V1_2 => self.affected_elements.len() + self.affected_libraries.len(),
+        };
+        mem::size_of::<u8>() + library_size_bytes(version) + mem::size_of::<u8>() + affected_size
+    }
+}
+
+impl<'a> SizeBytesVersioned for GEIn<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + library_size_bytes(version)
+            + count_size_bytes(version)
+            + self.element_numbers.len() * mem::size_of::<u8>()
+    }
+}
+
+impl<'a> SizeBytesVersioned for MEIn<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        library_size_bytes(version)
+            + count_size_bytes(version)
+            + self
+                .elements
+                .iter()
+                .map(|element| element.size_bytes_versioned(version))
+                .sum::<usize>()
+    }
+}
+
+impl SizeBytesVersioned for MediaInformation {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + serial_number_size_bytes(version)
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + self.media_name.size_bytes()
+            + mem::size_of::<u64>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u32>()
+            + mem::size_of::<u8>()
+    }
+}
+
+impl<'a> SizeBytesVersioned for EEIn<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        library_size_bytes(version)
+            + count_size_bytes(version)
+            + self
+                .elements
+                .iter()
+                .map(|element| element.size_bytes_versioned(version))
+                .sum::<usize>()
+    }
+}
+
+impl SizeBytesVersioned for EffectInformation {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + serial_number_size_bytes(version)
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + self.effect_name.size_bytes()
+            + mem::size_of::<u8>()
+            + self
+                .effect_parameter_names
+                .iter()
+                .map(|name| name.size_bytes())
+                .sum::<usize>()
+    }
+}
+
+impl<'a> SizeBytesVersioned for GLEI<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        self.library_id.size_bytes()
+            + mem::size_of::<u8>()
+            + count_size_bytes(version)
+            + self
+                .elements
+                .iter()
+                .map(|element| element.size_bytes_versioned(version))
+                .sum::<usize>()
+    }
+}
+
+impl SizeBytesVersioned for GenericInformation {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + serial_number_size_bytes(version)
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + self.name.size_bytes()
+            + mem::size_of::<u64>()
+    }
+}
+
+impl<'a> SizeBytesVersioned for GELT<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        let libraries_size = match Layout::of_size(version) {
+            Layout::V1_0 => self.library_numbers.len() * mem::size_of::<u8>(),
+            Layout::V1_1 | Layout::V1_2 => self.library_ids.len() * mem::size_of::<LibraryId>(),
+        };
+        mem::size_of::<u32>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + count_size_bytes(version)
+            + libraries_size
+    }
+}
+
+impl<'a> SizeBytesVersioned for ELTh<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + library_size_bytes(version)
+            + mem::size_of::<u32>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + self.thumbnail_buffer.len()
+    }
+}
+
+impl<'a> SizeBytesVersioned for GETh<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u32>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u8>()
+            + mem::size_of::<u8>()
+            + library_size_bytes(version)
+            + count_size_bytes(version)
+            + self.element_numbers.len() * mem::size_of::<u8>()
+    }
+}
+
+impl<'a> SizeBytesVersioned for EThn<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        mem::size_of::<u8>()
+            + library_size_bytes(version)
+            + mem::size_of::<u8>()
+            + mem::size_of::<u32>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + self.thumbnail_buffer.len()
+    }
+}
+
+impl SizeBytesVersioned for GVSr {
+    fn size_bytes_versioned(&self, _: Version) -> usize {
+        self.size_bytes()
+    }
+}
+
+impl<'a> SizeBytesVersioned for VSrc<'a> {
+    fn size_bytes_versioned(&self, _: Version) -> usize {
+        self.size_bytes()
+    }
+}
+
+impl SizeBytesVersioned for RqSt {
+    fn size_bytes_versioned(&self, _: Version) -> usize {
+        self.size_bytes()
+    }
+}
+
+impl<'a> SizeBytesVersioned for StFr<'a> {
+    fn size_bytes_versioned(&self, version: Version) -> usize {
+        let uuid_size = match Layout::of_size(version) {
+            Layout::V1_0 | Layout::V1_1 => 0,
+            Layout::V1_2 => self.media_server_uuid.len(),
+        };
+        let fragment_size = self.fragment.as_ref().map(|f| f.size_bytes()).unwrap_or(0);
+        uuid_size
+            + mem::size_of::<u16>()
+            + mem::size_of::<u32>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + mem::size_of::<u16>()
+            + fragment_size
+            + self.frame_buffer.len()
+    }
+}
+
+/// The layouts of versioned MSEX messages.
+///
+/// MSEX 1.2 is the latest layout. Later versions are read and written using the 1.2 layout, as the
+/// specification only appends new fields to existing messages and a `Message` reader skips any
+/// trailing data. Versions prior to MSEX 1.0 are not supported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Layout {
+    V1_0,
+    V1_1,
+    V1_2,
+}
+
+impl Layout {
+    /// The layout of messages of the given MSEX version.
+    fn of(version: Version) -> io::Result<Self> {
+        match version {
+            Version::V1_0 => Ok(Layout::V1_0),
+            Version::V1_1 => Ok(Layout::V1_1),
+            _ if version >= Version::V1_2 => Ok(Layout::V1_2),
+            _ => Err(unsupported_version(version)),
+        }
+    }
+
+    /// The layout used to determine the size of messages of the given MSEX version.
+    ///
+    /// Messages of unsupported versions can be neither written nor read, so they are sized using
+    /// the latest layout.
+    fn of_size(version: Version) -> Self {
+        Self::of(version).unwrap_or(Layout::V1_2)
+    }
+}
+
+/// Produces an error describing that the given MSEX version is not supported by a message.
+fn unsupported_version(version: Version) -> io::Error {
+    Error::UnsupportedVersion(version).into()
+}
+
+/// Write an element count whose size depends on the MSEX version.
+///
+/// Many element counts were widened from a `u8` to a `u16` in MSEX 1.2.
+fn write_count_versioned<W>(writer: W, len: usize, version: Version) -> io::Result<()>
+    where
+        W: WriteBytesExt,
+{
+    match Layout::of(version)? {
+        Layout::V1_0 | Layout::V1_1 => write_count_u8(writer, len),
+        Layout::V1_2 => write_count_u16(writer, len),
+    }
+}
+
+/// Read an element count whose size depends on the MSEX version.
+fn read_count_versioned<R: ReadBytesExt>(mut reader: R, version: Version) -> io::Result<usize> {
+    match Layout::of(version)? {
+        Layout::V1_0 | Layout::V1_1 => Ok(reader.read_u8()? as usize),
+        Layout::V1_2 => Ok(reader.read_u16::<LE>()? as usize),
+    }
+}
+
+/// The size of an element count whose size depends on the MSEX version.
+fn count_size_bytes(version: Version) -> usize {
+    match Layout::of_size(version) {
+        Layout::V1_0 | Layout::V1_1 => mem::size_of::<u8>(),
+        Layout::V1_2 => mem::size_of::<u16>(),
+    }
+}
+
+/// Write the library number in MSEX 1.0, or the library id in MSEX 1.1 and later.
+fn write_library<W>(mut writer: W, number: u8, id: LibraryId, version: Version) -> io::Result<()>
+    where
+        W: WriteBytesExt,
+{
+    match Layout::of(version)? {
+        Layout::V1_0 => writer.write_u8(number),
+        Layout::V1_1 | Layout::V1_2 => writer.write_bytes(id),
+    }
+}
+
+/// Read the library number in MSEX 1.0, or the library id in MSEX 1.1 and later.
+///
+/// The field that is not present within the given version is left as `0`.
+fn read_library<R: ReadBytesExt>(mut reader: R, version: Version) -> io::Result<(u8, LibraryId)> {
+    match Layout::of(version)? {
+        Layout::V1_0 => Ok((reader.read_u8()?, LibraryId::default())),
+        Layout::V1_1 | Layout::V1_2 => Ok((0, reader.read_bytes()?)),
+    }
+}
+
+/// The size of the library number or library id depending on the MSEX version.
+fn library_size_bytes(version: Version) -> usize {
+    match Layout::of_size(version) {
+        Layout::V1_0 => mem::size_of::<u8>(),
+        Layout::V1_1 | Layout::V1_2 => mem::size_of::<LibraryId>(),
+    }
+}
+
+/// The size of the element serial number introduced in MSEX 1.2.
+fn serial_number_size_bytes(version: Version) -> usize {
+    match Layout::of_size(version) {
+        Layout::V1_0 | Layout::V1_1 => 0,
+        Layout::V1_2 => mem::size_of::<u32>(),
+    }
+}
+
+/// Whether or not the bit representing `n` is set within the given bit array.
+fn bit_array_contains(bits: &[u8; 32], n: u8) -> bool {
+    bits[n as usize / 8] & (1 << (n % 8)) != 0
+}
+
+/// Read a buffer of **len** raw bytes into a new, owned buffer.
+fn read_owned_buffer<'a, R>(mut reader: R, len: usize) -> io::Result<Cow<'a, [u8]>>
+    where
+        R: ReadBytesExt,
+{
+    Limits::current().check_list_len(len)?;
+    let mut buffer = vec![0; len];
+    reader.read_exact(&mut buffer)?;
+    Ok(Cow::Owned(buffer))
+}
+
+/// Read a buffer of **len** raw bytes, borrowed from the front of the slice.
+fn read_borrowed_buffer<'a>(bytes: &mut &'a [u8], len: usize) -> io::Result<Cow<'a, [u8]>> {
+    protocol::read_borrowed_bytes(bytes, len).map(Cow::Borrowed)
+}
+
+/// Read the `ELTh` message, reading its thumbnail buffer with `read_buffer`.
+fn read_elth<'a, R, F>(mut reader: R, version: Version, read_buffer: F) -> io::Result<ELTh<'a>>
+    where
+        R: ReadBytesExt,
+        F: FnOnce(R, usize) -> io::Result<Cow<'a, [u8]>>,
+{
+    let library_type = reader.read_u8()?;
+    let (library_number, library_id) = read_library(&mut reader, version)?;
+    let thumbnail_format = reader.read_bytes()?;
+    let thumbnail_width = reader.read_u16::<LE>()?;
+    let thumbnail_height = reader.read_u16::<LE>()?;
+    let thumbnail_buffer_size = reader.read_u16::<LE>()?;
+    let thumbnail_buffer = read_buffer(reader, thumbnail_buffer_size as _)?;
+    let elth = ELTh {
+        library_type,
+        library_number,
+        library_id,
+        thumbnail_format,
+        thumbnail_width,
+        thumbnail_height,
+        thumbnail_buffer,
+    };
+    Ok(elth)
+}
+
+/// Read the `EThn` message, reading its thumbnail buffer with `read_buffer`.
+fn read_ethn<'a, R, F>(mut reader: R, version: Version, read_buffer: F) -> io::Result<EThn<'a>>
+    where
+        R: ReadBytesExt,
+        F: FnOnce(R, usize) -> io::Result<Cow<'a, [u8]>>,
+{
+    let library_type = reader.read_u8()?;
+    let (library_number, library_id) = read_library(&mut reader, version)?;
+    let element_number = reader.read_u8()?;
+    let thumbnail_format = reader.read_bytes()?;
+    let thumbnail_width = reader.read_u16::<LE>()?;
+    let thumbnail_height = reader.read_u16::<LE>()?;
+    let thumbnail_buffer_size = reader.read_u16::<LE>()?;
+    let thumbnail_buffer = read_buffer(reader, thumbnail_buffer_size as _)?;
+    let ethn = EThn {
+        library_type,
+        library_number,
+        library_id,
+        element_number,
+        thumbnail_format,
+        thumbnail_width,
+        thumbnail_height,
+        thumbnail_buffer,
+    };
+    Ok(ethn)
+}
+
+/// Read the `StFr` message, reading its frame buffer with `read_buffer`.
+fn read_stfr<'a, R, F>(mut reader: R, version: Version, read_buffer: F) -> io::Result<StFr<'a>>
+    where
+        R: ReadBytesExt,
+        F: FnOnce(R, usize) -> io::Result<Cow<'a, [u8]>>,
+{
+    let mut media_server_uuid = [0; 36];
+    let layout = Layout::of(version)?;
+    match layout {
+        Layout::V1_0 | Layout::V1_1 => (),
+        Layout::V1_2 => reader.read_exact(&mut media_server_uuid)?,
+    }
+    let source_identifier = reader.read_u16::<LE>()?;
+    let frame_format = reader.read_bytes()?;
+    let frame_width = reader.read_u16::<LE>()?;
+    let frame_height = reader.read_u16::<LE>()?;
+    let mut frame_buffer_size = reader.read_u16::<LE>()? as usize;
+    let mut fragment = None;
+    if layout == Layout::V1_2 && is_fragmented_format(frame_format) {
+        let fragment_size = mem::size_of::<Fragment>();
+        if frame_buffer_size < fragment_size {
+            let err_msg = "the frame buffer is too small to contain the fragment preamble";
+            return Err(Error::InvalidMessage(err_msg).into());
+        }
+        fragment = Some(reader.read_bytes()?);
+        frame_buffer_size -= fragment_size;
+    }
+    let frame_buffer = read_buffer(reader, frame_buffer_size)?;
+    let stfr = StFr {
+        media_server_uuid,
+        source_identifier,
+        frame_format,
+        frame_width,
+        frame_height,
+        fragment,
+        frame_buffer,
+    };
+    Ok(stfr)
+}
+
+/// Read **len** elements of type **T** into a new **Vec** using the layout of the given version.
+fn read_new_vec_versioned<R, T>(mut reader: R, len: usize, version: Version) -> io::Result<Vec<T>>
+    where
+        R: ReadBytesExt,
+        T: ReadFromBytesVersioned,
+{
+    Limits::current().check_list_len(len)?;
+    let mut vec = Vec::with_capacity(len);
+    for _ in 0..len {
+        vec.push(T::read_from_bytes_versioned(&mut reader, version)?);
+    }
+    Ok(vec)
+}
+
+#[test]
+fn test_cinf_message_write_read_bytes() {
+    let cinf = CInf {
+        supported_msex_versions: Cow::Borrowed(&[Version::V1_0, Version::V1_1, Version::V1_2]),
+        future_message_data: Cow::Borrowed(&[0xAB, 0xCD]),
+    };
+    let msex_header = Header {
+        citp_header: protocol::Header {
+            cookie: Cookie::new(protocol::Header::COOKIE),
+            version_major: 1,
+            version_minor: 0,
+            kind: protocol::Kind::default(),
+            message_size: 26 + cinf.size_bytes() as u32,
+            message_part_count: 1,
+            message_part: 0,
+            content_type: Cookie::new(Header::CONTENT_TYPE),
+        },
+        version_major: 1,
+        version_minor: 2,
+        content_type: Cookie::new(CInf::CONTENT_TYPE),
+    };
+    let msg = Message { msex_header, message: cinf };
+
+    let mut bytes = vec![];
+    bytes.write_bytes(&msg).unwrap();
+    assert_eq!(bytes.len(), msg.size_bytes());
+    assert_eq!(&bytes[26..], &[0x03, 0x00, 0x01, 0x01, 0x01, 0x02, 0x01, 0xAB, 0xCD]);
+
+    // Trailing bytes beyond the `message_size` must not be consumed.
+    bytes.push(0xFF);
+    let mut reader = bytes.as_slice();
+    let read = reader.read_bytes::<Message<CInf>>().unwrap();
+    assert_eq!(read, msg);
+    assert_eq!(reader, &[0xFF]);
+}
+
+#[test]
+fn test_sinf_write_read_bytes_versioned() {
+    let sinf = SInf {
+        uuid: *b"0daf5c96-33f6-4d9c-8b45-a9c82b3c04b4",
+        product_name: Ucs2String::new("Media Server \u{2122}").unwrap(),
+        product_version_major: 2,
+        product_version_minor: 5,
+        product_version_bugfix: 1,
+        supported_msex_versions: Cow::Borrowed(&[Version::V1_1, Version::V1_2]),
+        supported_library_types: 0b11,
+        thumbnail_formats: Cow::Owned(vec![Cookie::new(IMAGE_FORMAT_JPEG)]),
+        stream_formats: Cow::Owned(vec![Cookie::new(IMAGE_FORMAT_RGB8)]),
+        layer_dmx_sources: Cow::Owned(vec![CString::new("ArtNet/0/0/1").unwrap()]),
+    };
+    assert!(sinf.supports_library_type(LIBRARY_TYPE_MEDIA));
+    assert!(sinf.supports_library_type(LIBRARY_TYPE_EFFECTS));
+    assert!(!sinf.supports_library_type(LIBRARY_TYPE_CUES));
+
+    let mut bytes = vec![];
+    sinf.write_to_bytes_versioned(&mut bytes, Version::V1_2).unwrap();
+    assert_eq!(bytes.len(), sinf.size_bytes_versioned(Version::V1_2));
+    let read = SInf::read_from_bytes_versioned(bytes.as_slice(), Version::V1_2).unwrap();
+    assert_eq!(read, sinf);
+
+    // Fields introduced in MSEX 1.2 are not part of the 1.0 layout.
+    let mut bytes = vec![];
+    sinf.write_to_bytes_versioned(&mut bytes, Version::V1_0).unwrap();
+    assert_eq!(bytes.len(), sinf.size_bytes_versioned(Version::V1_0));
+    let read = SInf::read_from_bytes_versioned(bytes.as_slice(), Version::V1_0).unwrap();
+    assert_eq!(read.product_name, sinf.product_name);
+    assert_eq!(read.layer_dmx_sources, sinf.layer_dmx_sources);
+    assert!(read.supported_msex_versions.is_empty());
+
+    // Later versions use the 1.2 layout, while versions prior to 1.0 are not supported.
+    let v1_3 = Version { major: 1, minor: 3 };
+    let msg = Message::new(v1_3, sinf.clone());
+    let mut bytes = vec![];
+    bytes.write_bytes(&msg).unwrap();
+    assert_eq!(bytes.len(), msg.size_bytes());
+    let read = bytes.as_slice().read_bytes::<Message<SInf>>().unwrap();
+    assert_eq!(read, msg);
+    let v0_9 = Version { major: 0, minor: 9 };
+    assert!(sinf.write_to_bytes_versioned(vec![], v0_9).is_err());
+    assert!(SInf::read_from_bytes_versioned(&bytes[26..], v0_9).is_err());
+}
+
+#[test]
+fn test_lsta_write_read_bytes_versioned() {
+    let layer_status = LayerStatus {
+        layer_number: 0,
+        physical_output: 1,
+        media_library_number: 0,
+        media_library_type: LIBRARY_TYPE_MEDIA,
+        media_library_id: LibraryId {
+            level: 1,
+            sub_level_1: 4,
+            sub_level_2: 0,
+            sub_level_3: 0,
+        },
+        media_number: 3,
+        media_name: Ucs2String::new("clip.mov").unwrap(),
+        media_position: 120,
+        media_length: 300,
+        media_fps: 25,
+        layer_status_flags: LayerStatus::MEDIA_PLAYING | LayerStatus::MEDIA_PLAYBACK_LOOPING,
+    };
+    assert!(layer_status.is_playing());
+    assert!(layer_status.is_looping());
+    assert!(!layer_status.is_paused());
+
+    let lsta = LSta {
+        layer_statuses: Cow::Owned(vec![layer_status]),
+    };
+    for &version in &[Version::V1_0, Version::V1_2] {
+        let mut bytes = vec![];
+        lsta.write_to_bytes_versioned(&mut bytes, version).unwrap();
+        assert_eq!(bytes.len(), lsta.size_bytes_versioned(version));
+        let read = LSta::read_from_bytes_versioned(bytes.as_slice(), version).unwrap();
+        assert_eq!(read.layer_statuses[0].media_name.to_string(), "clip.mov");
+        if version == Version::V1_2 {
+            assert_eq!(read, lsta);
+        }
+    }
+}
+
+#[test]
+fn test_lsta_read_spec_layout() {
+    // A single layer playing media number 7 of library 3 (or library 1.4 in MSEX 1.2), laid out
+    // field by field as described by the MSEX specification.
+    let name = [b'a', 0, b'.', 0, b'm', 0, b'o', 0, b'v', 0, 0, 0];
+    let tail = [
+        0x78, 0x00, 0x00, 0x00, // MediaPosition: 120
+        0x2C, 0x01, 0x00, 0x00, // MediaLength: 300
+        0x19, // MediaFPS: 25
+        0x05, 0x00, 0x00, 0x00, // LayerStatusFlags: playing and looping
+    ];
+    let v1_0: Vec<u8> = [&[1, 2, 1, 3, 7][..], &name, &tail].concat();
+    let v1_2: Vec<u8> = [&[1, 2, 1, 1, 1, 4, 0, 0, 7][..], &name, &tail].concat();
+
+    let lsta = LSta::read_from_bytes_versioned(v1_0.as_slice(), Version::V1_0).unwrap();
+    let layer_status = &lsta.layer_statuses[0];
+    assert_eq!(layer_status.layer_number, 2);
+    assert_eq!(layer_status.physical_output, 1);
+    assert_eq!(layer_status.media_library_number, 3);
+    assert_eq!(layer_status.media_number, 7);
+    assert_eq!(layer_status.media_name.to_string(), "a.mov");
+    assert_eq!(lsta.size_bytes_versioned(Version::V1_0), v1_0.len());
+
+    let lsta = LSta::read_from_bytes_versioned(v1_2.as_slice(), Version::V1_2).unwrap();
+    let layer_status = &lsta.layer_statuses[0];
+    assert_eq!(layer_status.media_library_type, LIBRARY_TYPE_MEDIA);
+    assert_eq!(layer_status.media_library_id.sub_level_1, 4);
+    assert_eq!(layer_status.media_number, 7);
+    assert_eq!(layer_status.media_name.to_string(), "a.mov");
+    assert_eq!((layer_status.media_position, layer_status.media_length), (120, 300));
+    assert_eq!(layer_status.media_fps, 25);
+    assert!(layer_status.is_playing() && layer_status.is_looping());
+    let mut bytes = vec![];
+    lsta.write_to_bytes_versioned(&mut bytes, Version::V1_2).unwrap();
+    assert_eq!(bytes, v1_2);
+}
+
+#[test]
+fn test_requests_match_reply_and_nack() {
+    let mut requests = Requests::new();
+    let geth = Cookie::new(b"GETh");
+    let geli = Cookie::new(b"GELI");
+    let geth_kind = requests.request(geth);
+    let geli_kind = requests.request(geli);
+    assert_eq!(requests.len(), 2);
+
+    let header = |content_type: &[u8; 4], kind| Header {
+        citp_header: protocol::Header {
+            cookie: Cookie::new(protocol::Header::COOKIE),
+            version_major: 1,
+            version_minor: 0,
+            kind,
+            message_size: 0,
+            message_part_count: 1,
+            message_part: 0,
+            content_type: Cookie::new(Header::CONTENT_TYPE),
+        },
+        version_major: 1,
+        version_minor: 2,
+        content_type: Cookie::new(content_type),
+    };
+
+    // Unsolicited messages do not match any request.
+    let lsta = header(LSta::CONTENT_TYPE, protocol::Kind::default());
+    assert_eq!(requests.response(&lsta), None);
+
+    // Requests do not match themselves.
+    assert_eq!(requests.response(&header(GELI::CONTENT_TYPE, geli_kind)), None);
+
+    match requests.response(&header(Nack::CONTENT_TYPE, geth_kind.into_response())) {
+        Some(Response::Nack { request_content_type, .. }) => assert_eq!(request_content_type, geth),
+        response => panic!("unexpected response: {:?}", response),
+    }
+    match requests.response(&header(b"ELIn", geli_kind.into_response())) {
+        Some(Response::Reply { request_content_type, .. }) => {
+            assert_eq!(request_content_type, geli)
+        }
+        response => panic!("unexpected response: {:?}", response),
+    }
+    assert!(requests.is_empty());
+}
+
+#[test]
+fn test_element_library_messages_write_read_bytes_versioned() {
+    let geli = GELI {
+        library_type: LIBRARY_TYPE_MEDIA,
+        library_parent_id: LibraryId::default(),
+        library_numbers: Cow::Borrowed(&[0, 1, 2]),
+    };
+    let elin = ELIn {
+        library_type: LIBRARY_TYPE_MEDIA,
+        elements: Cow::Owned(vec![LibraryInformation {
+            number: 0,
+            id: LibraryId {
+                level: 1,
+                sub_level_1: 2,
+                sub_level_2: 0,
+                sub_level_3: 0,
+            },
+            serial_number: 7,
+            dmx_range_min: 0,
+            dmx_range_max: 9,
+            name: Ucs2String::new("Backgrounds").unwrap(),
+            library_count: 0,
+            element_count: 300,
+        }]),
+    };
+    let mut elup = ELUp {
+        library_type: LIBRARY_TYPE_MEDIA,
+        library_number: 0,
+        library_id: elin.elements[0].id,
+        update_flags: ELUp::EXISTING_ELEMENTS_UPDATED,
+        affected_elements: [0; 32],
+        affected_libraries: [0; 32],
+    };
+    elup.affected_elements[1] = 0b100;
+    assert!(elup.is_element_affected(10));
+    assert!(!elup.is_element_affected(11));
+
+    fn round_trip<T>(msg: &T, version: Version) -> T
+        where
+            T: WriteToBytesVersioned + ReadFromBytesVersioned + SizeBytesVersioned,
+    {
+        let mut bytes = vec![];
+        msg.write_to_bytes_versioned(&mut bytes, version).unwrap();
+        assert_eq!(bytes.len(), msg.size_bytes_versioned(version));
+        T::read_from_bytes_versioned(bytes.as_slice(), version).unwrap()
+    }
+
+    assert_eq!(round_trip(&geli, Version::V1_2), geli);
+    assert_eq!(round_trip(&elin, Version::V1_2), elin);
+    assert_eq!(round_trip(&elup, Version::V1_2), elup);
+    assert_eq!(round_trip(&geli, Version::V1_0).library_numbers, geli.library_numbers);
+    assert_eq!(round_trip(&elup, Version::V1_1).library_id, elup.library_id);
+
+    // The element count of 300 does not fit within the `u8` count of MSEX 1.1.
+    assert!(elin.write_to_bytes_versioned(vec![], Version::V1_1).is_err());
+}
+
+#[test]
+fn test_element_information_messages_write_read_bytes_versioned() {
+    let mein = MEIn {
+        library_number: 0,
+        library_id: LibraryId::default(),
+        elements: Cow::Owned(vec![MediaInformation {
+            number: 3,
+            serial_number: 11,
+            dmx_range_min: 3,
+            dmx_range_max: 3,
+            media_name: Ucs2String::new("loop.mov").unwrap(),
+            media_version_timestamp: 1_500_000_000,
+            media_width: 1920,
+            media_height: 1080,
+            media_length: 900,
+            media_fps: 30,
+        }]),
+    };
+    let eein = EEIn {
+        library_number: 2,
+        library_id: LibraryId::default(),
+        elements: Cow::Owned(vec![EffectInformation {
+            number: 0,
+            serial_number: 0,
+            dmx_range_min: 0,
+            dmx_range_max: 0,
+            effect_name: Ucs2String::new("Blur").unwrap(),
+            effect_parameter_names: vec![
+                Ucs2String::new("Radius").unwrap(),
+                Ucs2String::new("Angle").unwrap(),
+            ],
+        }]),
+    };
+
+    for &version in &[Version::V1_0, Version::V1_1, Version::V1_2] {
+        let mut bytes = vec![];
+        mein.write_to_bytes_versioned(&mut bytes, version).unwrap();
+        assert_eq!(bytes.len(), mein.size_bytes_versioned(version));
+        let read = MEIn::read_from_bytes_versioned(bytes.as_slice(), version).unwrap();
+        assert_eq!(read.elements[0].media_version_timestamp, 1_500_000_000);
+        assert_eq!(read.elements[0].media_width, 1920);
+
+        let mut bytes = vec![];
+        eein.write_to_bytes_versioned(&mut bytes, version).unwrap();
+        assert_eq!(bytes.len(), eein.size_bytes_versioned(version));
+        let read = EEIn::read_from_bytes_versioned(bytes.as_slice(), version).unwrap();
+        assert_eq!(read.elements, eein.elements);
+    }
+
+    // GLEI was introduced in MSEX 1.1.
+    let glei = GLEI {
+        library_id: LibraryId::default(),
+        library_type: LIBRARY_TYPE_CUES,
+        elements: Cow::Owned(vec![]),
+    };
+    assert!(glei.write_to_bytes_versioned(vec![], Version::V1_0).is_err());
+}
+
+#[test]
+fn test_thumbnail_messages_write_read_bytes_versioned() {
+    let geth = GETh {
+        thumbnail_format: Cookie::new(IMAGE_FORMAT_JPEG),
+        thumbnail_width: 128,
+        thumbnail_height: 72,
+        thumbnail_flags: GETh::PRESERVE_ASPECT_RATIO,
+        library_type: LIBRARY_TYPE_MEDIA,
+        library_number: 1,
+        library_id: LibraryId::default(),
+        element_numbers: Cow::Borrowed(&[]),
+    };
+    assert!(geth.preserve_aspect_ratio());
+    let ethn = EThn {
+        library_type: LIBRARY_TYPE_MEDIA,
+        library_number: 0,
+        library_id: LibraryId {
+            level: 1,
+            sub_level_1: 1,
+            sub_level_2: 0,
+            sub_level_3: 0,
+        },
+        element_number: 4,
+        thumbnail_format: Cookie::new(IMAGE_FORMAT_PNG),
+        thumbnail_width: 128,
+        thumbnail_height: 72,
+        thumbnail_buffer: Cow::Borrowed(&[0x89, b'P', b'N', b'G']),
+    };
+
+    let mut bytes = vec![];
+    geth.write_to_bytes_versioned(&mut bytes, Version::V1_0).unwrap();
+    assert_eq!(bytes.len(), geth.size_bytes_versioned(Version::V1_0));
+    let read = GETh::read_from_bytes_versioned(bytes.as_slice(), Version::V1_0).unwrap();
+    assert_eq!(read, geth);
+
+    let mut bytes = vec![];
+    ethn.write_to_bytes_versioned(&mut bytes, Version::V1_2).unwrap();
+    assert_eq!(bytes.len(), ethn.size_bytes_versioned(Version::V1_2));
+    let read = EThn::read_from_bytes_versioned(bytes.as_slice(), Version::V1_2).unwrap();
+    assert_eq!(read, ethn);
+}
+
+#[test]
+fn test_stream_messages_write_read_bytes_versioned() {
+    let vsrc = VSrc {
+        sources: Cow::Owned(vec![SourceInformation {
+            source_identifier: 1,
+            source_name: Ucs2String::new("Output 1").unwrap(),
+            physical_output: 0,
+            layer_number: 0xFF,
+            flags: SourceInformation::WITHOUT_EFFECTS,
+            width: 1920,
+            height: 1080,
+        }]),
+    };
+    let mut bytes = vec![];
+    vsrc.write_to_bytes_versioned(&mut bytes, Version::V1_2).unwrap();
+    assert_eq!(bytes.len(), vsrc.size_bytes_versioned(Version::V1_2));
+    let read = VSrc::read_from_bytes_versioned(bytes.as_slice(), Version::V1_2).unwrap();
+    assert_eq!(read, vsrc);
+
+    let stfr = StFr {
+        media_server_uuid: *b"0daf5c96-33f6-4d9c-8b45-a9c82b3c04b4",
+        source_identifier: 1,
+        frame_format: Cookie::new(IMAGE_FORMAT_FRAGMENTED_JPEG),
+        frame_width: 320,
+        frame_height: 180,
+        fragment: Some(Fragment {
+            frame_index: 42,
+            fragment_count: 3,
+            fragment_index: 1,
+            fragment_byte_offset: 1400,
+        }),
+        frame_buffer: Cow::Borrowed(&[1, 2, 3, 4]),
+    };
+    let mut bytes = vec![];
+    stfr.write_to_bytes_versioned(&mut bytes, Version::V1_2).unwrap();
+    assert_eq!(bytes.len(), stfr.size_bytes_versioned(Version::V1_2));
+    // The frame buffer size includes the fragment preamble.
+    assert_eq!(&bytes[46..48], &[16, 0]);
+    let read = StFr::read_from_bytes_versioned(bytes.as_slice(), Version::V1_2).unwrap();
+    assert_eq!(read, stfr);
+
+    // A fragmented frame format requires the fragment preamble.
+    let stfr = StFr { fragment: None, ..stfr };
+    assert!(stfr.write_to_bytes_versioned(vec![], Version::V1_2).is_err());
+
+    // Fragmented frame formats do not exist prior to MSEX 1.2, so the buffer is not fragmented.
+    let mut bytes = vec![];
+    stfr.write_to_bytes_versioned(&mut bytes, Version::V1_1).unwrap();
+    assert_eq!(bytes.len(), stfr.size_bytes_versioned(Version::V1_1));
+    assert_eq!(&bytes[10..12], &[4, 0]);
+    let read = StFr::read_from_bytes_versioned(bytes.as_slice(), Version::V1_1).unwrap();
+    assert_eq!(read.fragment, None);
+    assert_eq!(read.frame_buffer, stfr.frame_buffer);
+}
+
+#[test]
+fn test_message_request_response() {
+    let mut requests = Requests::new();
+    let request = Message::request(Version::V1_2, GVSr, &mut requests);
+    assert_eq!(request.msex_header.version(), Version::V1_2);
+    assert_eq!(request.msex_header.citp_header.message_size, 26);
+    let request_index = request.msex_header.citp_header.kind.request_index();
+    assert_eq!(request_index.map(|index| index.get()), Some(1));
+
+    let vsrc = VSrc { sources: Cow::Owned(vec![]) };
+    let response = Message::response(Version::V1_2, vsrc, &request.msex_header);
+    let mut buffer = vec![];
+    buffer.write_bytes(&response).unwrap();
+    assert_eq!(response.msex_header.citp_header.message_size as usize, buffer.len());
+    assert_eq!(&buffer[6..8], &[1, 0]);
+    // The response is distinguished from a request by its content type when read.
+    let read: Header = buffer.as_slice().read_bytes().unwrap();
+    assert_eq!(read, response.msex_header);
+    assert_eq!(read.citp_header.kind.in_response_to(), request_index);
+    match requests.response(&read) {
+        Some(Response::Reply { request_index: 1, request_content_type }) => {
+            assert_eq!(request_content_type, GVSr::CONTENT_TYPE);
+        }
+        response => panic!("unexpected response: {:?}", response),
+    }
+
+    // The kind must match the variant implied by the content type when written.
+    let mut mismatched = response.msex_header;
+    mismatched.citp_header.kind = request.msex_header.citp_header.kind;
+    let err = Error::from(vec![].write_bytes(mismatched).unwrap_err());
+    assert!(err.is_protocol_error());
+    let mut mismatched = request.msex_header;
+    mismatched.citp_header.kind = response.msex_header.citp_header.kind;
+    let err = Error::from(vec![].write_bytes(mismatched).unwrap_err());
+    assert!(err.is_protocol_error());
+    // Requests are read back unchanged.
+    let mut buffer = vec![];
+    buffer.write_bytes(request.msex_header).unwrap();
+    let read: Header = buffer.as_slice().read_bytes().unwrap();
+    assert_eq!(read, request.msex_header);
+}
+
+#[test]
+fn test_message_read_slice_borrowed() {
+    use crate::protocol::ReadSlice;
+
+    let elth = ELTh {
+        library_type: LIBRARY_TYPE_MEDIA,
+        library_number: 0,
+        library_id: LibraryId {
+            level: 1,
+            sub_level_1: 2,
+            sub_level_2: 0,
+            sub_level_3: 0,
+        },
+        thumbnail_format: Cookie::new(IMAGE_FORMAT_RGB8),
+        thumbnail_width: 2,
+        thumbnail_height: 1,
+        thumbnail_buffer: Cow::Owned(vec![0xFF, 0, 0, 0, 0xFF, 0]),
+    };
+    let ethn = EThn {
+        library_type: LIBRARY_TYPE_MEDIA,
+        library_number: 2,
+        library_id: LibraryId::default(),
+        element_number: 4,
+        thumbnail_format: Cookie::new(IMAGE_FORMAT_JPEG),
+        thumbnail_width: 128,
+        thumbnail_height: 72,
+        thumbnail_buffer: Cow::Owned((0..=255).collect()),
+    };
+    let stfr = StFr {
+        media_server_uuid: *b"0daf5c96-33f6-4d9c-8b45-a9c82b3c04b4",
+        source_identifier: 1,
+        frame_format: Cookie::new(IMAGE_FORMAT_FRAGMENTED_PNG),
+        frame_width: 320,
+        frame_height: 180,
+        fragment: Some(Fragment {
+            frame_index: 7,
+            fragment_count: 2,
+            fragment_index: 0,
+            fragment_byte_offset: 0,
+        }),
+        frame_buffer: Cow::Owned(vec![0x89, b'P', b'N', b'G']),
+    };
+    let mut buffer = vec![];
+    buffer.write_bytes(Message::new(Version::V1_1, elth.clone())).unwrap();
+    buffer.write_bytes(Message::new(Version::V1_0, ethn.clone())).unwrap();
+    buffer.write_bytes(Message::new(Version::V1_2, stfr.clone())).unwrap();
+
+    let mut bytes = &buffer[..];
+    let msg: Message<ELTh> = bytes.read_slice().unwrap();
+    match msg.message.thumbnail_buffer {
+        Cow::Borrowed(buffer) => assert_eq!(buffer, &elth.thumbnail_buffer[..]),
+        Cow::Owned(_) => panic!("expected the thumbnail buffer to be borrowed"),
+    }
+    assert_eq!(msg.message, elth);
+    let msg: Message<EThn> = bytes.read_slice().unwrap();
+    match msg.message.thumbnail_buffer {
+        Cow::Borrowed(buffer) => assert_eq!(buffer, &ethn.thumbnail_buffer[..]),
+        Cow::Owned(_) => panic!("expected the thumbnail buffer to be borrowed"),
+    }
+    assert_eq!(msg.message, ethn);
+    let msg: Message<StFr> = bytes.read_slice().unwrap();
+    match msg.message.frame_buffer {
+        Cow::Borrowed(buffer) => assert_eq!(buffer, &stfr.frame_buffer[..]),
+        Cow::Owned(_) => panic!("expected the frame buffer to be borrowed"),
+    }
+    assert_eq!(msg.message, stfr);
+    assert!(bytes.is_empty());
+}
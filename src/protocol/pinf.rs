@@ -1,11 +1,9 @@
 use std::{io, mem};
 use std::ffi::CString;
 
-use byteorder::LittleEndian;
-
-use protocol::{
-    self, LE, ReadBytes, ReadBytesExt, ReadFromBytes, SizeBytes, WriteBytes,
-    WriteBytesExt, WriteToBytes,
+use crate::protocol::{
+    self, ContentType, Cookie, LE, ReadBytes, ReadBytesExt, ReadFromBytes, SizeBytes, WriteBytes,
+    WriteBytesExt, WriteToBytes, limits,
 };
 
 /// The old port originally used for broadcast.
@@ -22,16 +20,18 @@ pub const MULTICAST_ADDR: [u8; 4] = [239, 224, 0, 180];
 
 /// The PINF layer provides a standard, single, header used at the start of all PINF packets.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Header {
     /// The CITP header. CITP ContentType is "PINF".
     pub citp_header: protocol::Header,
     /// A cookie defining which PINF message it is.
-    pub content_type: u32,
+    pub content_type: Cookie,
 }
 
 /// Layout of PINF messages.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Message<T> {
     /// The PINF header - the base header with the PINF content type.
@@ -47,10 +47,12 @@ pub struct Message<T> {
 /// PLoc message is multicasted instead. The PNam message is useful though, as a message
 /// transferred from a peer connected to a listening peer.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct PNam {
     /// The display name of the peer (null terminated). This should be anything from a user defined
     /// alias for the peer of the name of the product, or a combination.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub name: CString,
 }
 
@@ -62,35 +64,68 @@ pub struct PNam {
 /// additional connections should be actively refused. The `type` field instructs the receiver what
 /// kind of peer it is and the name and state fields provide display name and information.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct PLoc {
     /// The port on which the peer is listening for incoming TCP connections. `0` if not listening.
     pub listening_tcp_port: u16,
     /// Can be "LightingConsole", "MediaServer" or "Visualiser".
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub kind: CString,
     /// The display name of the peer. Corresponds to the `pinf::PNam::name` field.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub name: CString,
     /// The display state of the peer. This can be descriptive string presentable to the user such
     /// as "Idle", "Running", etc.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub state: CString,
 }
 
 impl Header {
-    pub const CONTENT_TYPE: &'static [u8] = b"PINF";
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"PINF";
 }
 
 impl PNam {
-    pub const CONTENT_TYPE: &'static [u8] = b"PNam";
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"PNam";
 }
 
 impl PLoc {
-    pub const CONTENT_TYPE: &'static [u8] = b"PLoc";
+    pub const CONTENT_TYPE: &'static [u8; 4] = b"PLoc";
+}
+
+impl ContentType for PNam {
+    const CONTENT_TYPE: &'static [u8; 4] = PNam::CONTENT_TYPE;
+}
+
+impl ContentType for PLoc {
+    const CONTENT_TYPE: &'static [u8; 4] = PLoc::CONTENT_TYPE;
+}
+
+impl<T> Message<T>
+    where
+        T: ContentType + SizeBytes,
+{
+    /// Create a new PINF message, filling in the cookies of all headers and the `message_size` of
+    /// the CITP header.
+    pub fn new(message: T) -> Self {
+        let citp_content_type = Cookie::new(Header::CONTENT_TYPE);
+        let pinf_header = Header {
+            citp_header: protocol::Header::new(citp_content_type, Default::default(), 0),
+            content_type: Cookie::new(T::CONTENT_TYPE),
+        };
+        let mut msg = Message {
+            pinf_header,
+            message,
+        };
+        msg.pinf_header.citp_header.message_size = msg.size_bytes() as u32;
+        msg
+    }
 }
 
 impl WriteToBytes for Header {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.citp_header)?;
-        writer.write_u32::<LE>(self.content_type)?;
+        writer.write_bytes(self.citp_header)?;
+        writer.write_bytes(self.content_type)?;
         Ok(())
     }
 }
@@ -100,7 +135,7 @@ impl<T> WriteToBytes for Message<T>
         T: WriteToBytes,
 {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.pinf_header)?;
+        writer.write_bytes(self.pinf_header)?;
         writer.write_bytes(&self.message)?;
         Ok(())
     }
@@ -127,19 +162,31 @@ impl ReadFromBytes for Header {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
         let header = Header {
             citp_header: reader.read_bytes()?,
-            content_type: reader.read_u32::<LittleEndian>()?,
+            content_type: reader.read_bytes()?,
         };
         Ok(header)
     }
 }
 
-impl ReadFromBytes for Message<PLoc> {
+impl<T> ReadFromBytes for Message<T>
+    where
+        T: ContentType + ReadFromBytes,
+{
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
-        let msg = Message::<PLoc> {
-            pinf_header: reader.read_bytes()?,
-            message: reader.read_bytes::<PLoc>()?,
+        let pinf_header: Header = reader.read_bytes()?;
+        protocol::check_cookie(pinf_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(pinf_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(pinf_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = pinf_header.citp_header.message_size;
+        let headers_size = pinf_header.size_bytes();
+        let message = limits::read_message_body(reader, message_size, headers_size, |r| {
+            r.read_bytes()
+        })?;
+        let msg = Message {
+            pinf_header,
+            message,
         };
-        return Ok(msg);
+        Ok(msg)
     }
 }
 
@@ -167,6 +214,21 @@ impl ReadFromBytes for PLoc {
     }
 }
 
+impl SizeBytes for Header {
+    fn size_bytes(&self) -> usize {
+        self.citp_header.size_bytes() + mem::size_of::<u32>()
+    }
+}
+
+impl<T> SizeBytes for Message<T>
+    where
+        T: SizeBytes,
+{
+    fn size_bytes(&self) -> usize {
+        self.pinf_header.size_bytes() + self.message.size_bytes()
+    }
+}
+
 impl SizeBytes for PNam {
     fn size_bytes(&self) -> usize {
         self.name.size_bytes()
@@ -198,8 +260,19 @@ fn test_ploc_message_read_bytes() {
     let citp_header = buffer.as_slice().read_bytes::<Message<PLoc>>();
 
     assert!(citp_header.is_ok());
-    assert_eq!(
-        citp_header.unwrap().pinf_header.content_type.to_le_bytes(),
-        *b"PLoc"
-    );
+    assert_eq!(citp_header.unwrap().pinf_header.content_type, PLoc::CONTENT_TYPE);
+}
+
+#[test]
+fn test_message_read_bytes_checks_content_type() {
+    let name = CString::new("Peer").unwrap();
+    let mut buffer = vec![];
+    buffer.extend_from_slice(b"CITP\x01\x00\x00\x00\x1d\x00\x00\x00\x01\x00\x00\x00PINFPNam");
+    buffer.write_bytes(&name).unwrap();
+
+    let pnam = buffer.as_slice().read_bytes::<Message<PNam>>().unwrap();
+    assert_eq!(pnam.message.name, name);
+
+    let err = buffer.as_slice().read_bytes::<Message<PLoc>>().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
 }
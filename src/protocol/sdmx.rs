@@ -1,26 +1,28 @@
-use std::{self, io, mem};
+use std::{io, mem};
 use std::borrow::Cow;
 use std::ffi::CString;
 
-use protocol::{
-    self, LE, ReadBytes, ReadBytesExt, ReadFromBytes, SizeBytes, WriteBytes,
-    WriteBytesExt, WriteToBytes,
+use crate::protocol::{
+    self, ContentType, Cookie, LE, ReadBytes, ReadBytesExt, ReadFromBytes, ReadFromSlice, ReadSlice,
+    SizeBytes, WriteBytes, WriteBytesExt, WriteToBytes, limits,
 };
 
 /// ## The SDMX header.
 ///
 /// The SDMX layer provides a standard, single, header used at the start of all SDMX packets.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Header {
     /// The CITP header. CITP ContentType is "SDMX".
     pub citp_header: protocol::Header,
     /// Cookie defining which SDMX message it is.
-    pub content_type: u32,
+    pub content_type: Cookie,
 }
 
 /// SDMX messages are always prefixed with a CITP SDMX header.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Message<T> {
     /// The base header along with the SDMX content type.
@@ -34,6 +36,7 @@ pub struct Message<T> {
 /// The capabilities message can be sent by a peer to the remote peer upon connect to inform the
 /// remote peer about the capabilities.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Capa<'a> {
     /// A list of capabilities.
@@ -55,11 +58,13 @@ pub struct Capa<'a> {
 /// The universe name message can be sent by a DMX transmitting peer in order to provide the other
 /// end with a displayable name of a universe.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct UNam {
     /// `0`-based index of the universe.
     pub universe_index: u8,
     /// Name of the universe.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub universe_name: CString,
 }
 
@@ -70,9 +75,11 @@ pub struct UNam {
 /// contents and results of this message is not part of the CITP specification - it must be agreed
 /// upon a priori.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct EnId {
     /// Encryption scheme identifier.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub identifier: CString,
 }
 
@@ -83,6 +90,7 @@ pub struct EnId {
 /// to blind DMX whenever such is present and to revert back after some short timeout when it is no
 /// longer transmitted.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ChBk<'a> {
     /// Set to `1` for blind preview dmx, `0` otherwise.
@@ -100,6 +108,7 @@ pub struct ChBk<'a> {
 /// The Channel List message transmits a set of non-consecutive DMX levels. This message should
 /// only be sent if the remote peer has acknowledged supporting it in a Capabilities message.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ChLs<'a> {
     /// The list of channel levels.
@@ -108,14 +117,15 @@ pub struct ChLs<'a> {
 
 /// A single channel level within a list specified via a `ChLs` message.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ChannelLevel {
     /// `0`-based index of the universe.
-    universe_index: u8,
+    pub universe_index: u8,
     /// `0`-based index of the channel in the universe.
-    channel: u16,
+    pub channel: u16,
     /// DMX channel level.
-    channel_level: u8,
+    pub channel_level: u8,
 }
 
 /// ## SDMX / SXSr - Set External Source message.
@@ -125,6 +135,7 @@ pub struct ChannelLevel {
 /// external source specified should be treated as the base universe of a consecutive series of
 /// universes.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct SXSr {
     /// DMX-source connction string.
@@ -137,7 +148,8 @@ pub struct SXSr {
     ///   channel of the first universe.
     /// - **ETC Net2**: "EtcNet2/<channel>", ie. "ETCNet2/1" is the first ETCNet2 channel.
     /// - **MA-Net**: "MANet/<type>/<universe>/<channel>", ie. "MANet/2/0/1" is the first channel
-    /// of the first MA-Net 2 universe.
+    ///   of the first MA-Net 2 universe.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub connection_string: CString,
 }
 
@@ -146,11 +158,13 @@ pub struct SXSr {
 /// The Set External Universe Source message functions like the Set External Source mssage, but on
 /// a universe level rather than a global level.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Sxus {
     /// `0`-based index of the universe.
     pub universe_index: u8,
     /// DMX-source connection string - as the SXSr message.
+    #[cfg_attr(feature = "serde", serde(with = "crate::protocol::serde_impl::cstring"))]
     pub connection_string: CString,
 }
 
@@ -194,10 +208,59 @@ impl Sxus {
     pub const CONTENT_TYPE: &'static [u8; 4] = b"SXUS";
 }
 
+impl<'a> ContentType for Capa<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = Capa::CONTENT_TYPE;
+}
+
+impl ContentType for UNam {
+    const CONTENT_TYPE: &'static [u8; 4] = UNam::CONTENT_TYPE;
+}
+
+impl ContentType for EnId {
+    const CONTENT_TYPE: &'static [u8; 4] = EnId::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for ChBk<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = ChBk::CONTENT_TYPE;
+}
+
+impl<'a> ContentType for ChLs<'a> {
+    const CONTENT_TYPE: &'static [u8; 4] = ChLs::CONTENT_TYPE;
+}
+
+impl ContentType for SXSr {
+    const CONTENT_TYPE: &'static [u8; 4] = SXSr::CONTENT_TYPE;
+}
+
+impl ContentType for Sxus {
+    const CONTENT_TYPE: &'static [u8; 4] = Sxus::CONTENT_TYPE;
+}
+
+impl<T> Message<T>
+    where
+        T: ContentType + SizeBytes,
+{
+    /// Create a new SDMX message, filling in the cookies of all headers and the `message_size` of
+    /// the CITP header.
+    pub fn new(message: T) -> Self {
+        let citp_content_type = Cookie::new(Header::CONTENT_TYPE);
+        let sdmx_header = Header {
+            citp_header: protocol::Header::new(citp_content_type, Default::default(), 0),
+            content_type: Cookie::new(T::CONTENT_TYPE),
+        };
+        let mut msg = Message {
+            sdmx_header,
+            message,
+        };
+        msg.sdmx_header.citp_header.message_size = msg.size_bytes() as u32;
+        msg
+    }
+}
+
 impl WriteToBytes for Header {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.citp_header)?;
-        writer.write_u32::<LE>(self.content_type)?;
+        writer.write_bytes(self.citp_header)?;
+        writer.write_bytes(self.content_type)?;
         Ok(())
     }
 }
@@ -207,7 +270,7 @@ impl<T> WriteToBytes for Message<T>
         T: WriteToBytes,
 {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_bytes(&self.sdmx_header)?;
+        writer.write_bytes(self.sdmx_header)?;
         writer.write_bytes(&self.message)?;
         Ok(())
     }
@@ -215,11 +278,7 @@ impl<T> WriteToBytes for Message<T>
 
 impl<'a> WriteToBytes for Capa<'a> {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        if self.capabilities.len() > std::u16::MAX as usize {
-            let err_msg = "the number of capabilities exceeds the maximum possible `u16` value";
-            return Err(io::Error::new(io::ErrorKind::InvalidData, err_msg));
-        }
-        writer.write_u16::<LE>(self.capabilities.len() as u16)?;
+        protocol::write_count_u16(&mut writer, self.capabilities.len())?;
         for &cap in self.capabilities.iter() {
             writer.write_u16::<LE>(cap)?;
         }
@@ -247,7 +306,7 @@ impl<'a> WriteToBytes for ChBk<'a> {
         writer.write_u8(self.blind)?;
         writer.write_u8(self.universe_index)?;
         writer.write_u16::<LE>(self.first_channel)?;
-        writer.write_u16::<LE>(self.channel_levels.len() as _)?;
+        protocol::write_count_u16(&mut writer, self.channel_levels.len())?;
         for &lvl in self.channel_levels.iter() {
             writer.write_u8(lvl)?;
         }
@@ -266,7 +325,7 @@ impl WriteToBytes for ChannelLevel {
 
 impl<'a> WriteToBytes for ChLs<'a> {
     fn write_to_bytes<W: WriteBytesExt>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u16::<LE>(self.channel_levels.len() as _)?;
+        protocol::write_count_u16(&mut writer, self.channel_levels.len())?;
         for ch in self.channel_levels.iter() {
             writer.write_bytes(ch)?;
         }
@@ -289,6 +348,40 @@ impl WriteToBytes for Sxus {
     }
 }
 
+impl ReadFromBytes for Header {
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let citp_header = reader.read_bytes()?;
+        let content_type = reader.read_bytes()?;
+        let header = Header {
+            citp_header,
+            content_type,
+        };
+        Ok(header)
+    }
+}
+
+impl<T> ReadFromBytes for Message<T>
+    where
+        T: ContentType + ReadFromBytes,
+{
+    fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
+        let sdmx_header: Header = reader.read_bytes()?;
+        protocol::check_cookie(sdmx_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(sdmx_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(sdmx_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = sdmx_header.citp_header.message_size;
+        let headers_size = sdmx_header.size_bytes();
+        let message = limits::read_message_body(reader, message_size, headers_size, |r| {
+            r.read_bytes()
+        })?;
+        let msg = Message {
+            sdmx_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
 impl ReadFromBytes for Capa<'static> {
     fn read_from_bytes<R: ReadBytesExt>(mut reader: R) -> io::Result<Self> {
         let capability_count: u16 = reader.read_bytes()?;
@@ -382,6 +475,82 @@ impl ReadFromBytes for Sxus {
     }
 }
 
+impl<'a, T> ReadFromSlice<'a> for Message<T>
+    where
+        T: ContentType + ReadFromSlice<'a>,
+{
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let sdmx_header: Header = bytes.read_bytes()?;
+        protocol::check_cookie(sdmx_header.citp_header.cookie, protocol::Header::COOKIE)?;
+        protocol::check_cookie(sdmx_header.citp_header.content_type, Header::CONTENT_TYPE)?;
+        protocol::check_cookie(sdmx_header.content_type, T::CONTENT_TYPE)?;
+        let message_size = sdmx_header.citp_header.message_size;
+        let headers_size = sdmx_header.size_bytes();
+        let message = limits::read_message_body_slice(bytes, message_size, headers_size, |b| {
+            b.read_slice()
+        })?;
+        let msg = Message {
+            sdmx_header,
+            message,
+        };
+        Ok(msg)
+    }
+}
+
+impl<'a> ReadFromSlice<'a> for Capa<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let capability_count: u16 = bytes.read_bytes()?;
+        let capabilities = protocol::read_cow_u16s(bytes, capability_count as _)?;
+        let capabilities = Capa { capabilities };
+        Ok(capabilities)
+    }
+}
+
+impl<'a> ReadFromSlice<'a> for ChBk<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let blind = bytes.read_u8()?;
+        let universe_index = bytes.read_u8()?;
+        let first_channel = bytes.read_u16::<LE>()?;
+        let channel_level_count = bytes.read_u16::<LE>()?;
+        let channel_levels = protocol::read_borrowed_bytes(bytes, channel_level_count as _)?;
+        let channel_levels = Cow::Borrowed(channel_levels);
+        let chbk = ChBk {
+            blind,
+            universe_index,
+            first_channel,
+            channel_levels,
+        };
+        Ok(chbk)
+    }
+}
+
+/// The in-memory layout of **ChannelLevel** does not match the wire layout, so the channel levels
+/// are always read into `Cow::Owned` data.
+impl<'a> ReadFromSlice<'a> for ChLs<'a> {
+    fn read_from_slice(bytes: &mut &'a [u8]) -> io::Result<Self> {
+        let channel_level_count = bytes.read_u16::<LE>()?;
+        let channel_levels = protocol::read_new_vec(bytes, channel_level_count as _)?;
+        let channel_levels = Cow::Owned(channel_levels);
+        let chls = ChLs { channel_levels };
+        Ok(chls)
+    }
+}
+
+impl SizeBytes for Header {
+    fn size_bytes(&self) -> usize {
+        self.citp_header.size_bytes() + mem::size_of::<u32>()
+    }
+}
+
+impl<T> SizeBytes for Message<T>
+    where
+        T: SizeBytes,
+{
+    fn size_bytes(&self) -> usize {
+        self.sdmx_header.size_bytes() + self.message.size_bytes()
+    }
+}
+
 impl<'a> SizeBytes for Capa<'a> {
     fn size_bytes(&self) -> usize {
         mem::size_of::<u16>() + self.capabilities.len() * mem::size_of::<u16>()
@@ -410,9 +579,15 @@ impl<'a> SizeBytes for ChBk<'a> {
     }
 }
 
+impl SizeBytes for ChannelLevel {
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<u8>() + mem::size_of::<u16>() + mem::size_of::<u8>()
+    }
+}
+
 impl<'a> SizeBytes for ChLs<'a> {
     fn size_bytes(&self) -> usize {
-        mem::size_of::<u16>() + self.channel_levels.len() * mem::size_of::<ChannelLevel>()
+        mem::size_of::<u16>() + self.channel_levels.iter().map(|ch| ch.size_bytes()).sum::<usize>()
     }
 }
 
@@ -427,3 +602,29 @@ impl SizeBytes for Sxus {
         mem::size_of::<u8>() + self.connection_string.size_bytes()
     }
 }
+
+#[test]
+fn test_message_read_slice_borrowed() {
+    let chbk = ChBk {
+        blind: 0,
+        universe_index: 1,
+        first_channel: 0,
+        channel_levels: Cow::Owned((0..=255).collect()),
+    };
+    let capa = Capa {
+        capabilities: Cow::Borrowed(&[Capa::CHANNEL_LIST, Capa::EXTERNAL_SOURCE]),
+    };
+    let mut buffer = vec![];
+    buffer.write_bytes(Message::new(&chbk)).unwrap();
+    buffer.write_bytes(Message::new(&capa)).unwrap();
+
+    let mut bytes = &buffer[..];
+    let msg: Message<ChBk> = bytes.read_slice().unwrap();
+    match msg.message.channel_levels {
+        Cow::Borrowed(levels) => assert_eq!(levels, &chbk.channel_levels[..]),
+        Cow::Owned(_) => panic!("expected the channel levels to be borrowed"),
+    }
+    let msg: Message<Capa> = bytes.read_slice().unwrap();
+    assert_eq!(msg.message, capa);
+    assert!(bytes.is_empty());
+}
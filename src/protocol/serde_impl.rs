@@ -0,0 +1,160 @@
+//! ## Serde Support.
+//!
+//! **Serialize** and **Deserialize** implementations for the protocol types that cannot derive
+//! them, along with the `serde(with = "...")` modules used by fields of foreign types.
+//!
+//! Cookies are serialized as four character strings where each character represents a single byte
+//! (i.e. ISO-8859-1), so that any cookie may be serialized and deserialized unchanged. Strings
+//! containing invalid UTF-8 or UTF-16 are serialized with the replacement character in place of the
+//! invalid sequences.
+
+use serde::de::{self, Deserialize, Deserializer, Unexpected};
+use serde::ser::{Serialize, Serializer};
+
+use crate::protocol::{Cookie, Ucs2String};
+
+impl Serialize for Cookie {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let string: String = self.as_bytes().iter().map(|&byte| byte as char).collect();
+        serializer.serialize_str(&string)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cookie {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let mut bytes = [0u8; 4];
+        let mut chars = string.chars();
+        for byte in bytes.iter_mut() {
+            *byte = match chars.next().map(u32::from) {
+                Some(ch) if ch <= 0xFF => ch as u8,
+                _ => return Err(de::Error::invalid_value(Unexpected::Str(&string), &"a cookie")),
+            };
+        }
+        if chars.next().is_some() {
+            return Err(de::Error::invalid_value(Unexpected::Str(&string), &"a cookie"));
+        }
+        Ok(Cookie(bytes))
+    }
+}
+
+impl Serialize for Ucs2String {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ucs2String {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        Ucs2String::new(string).map_err(de::Error::custom)
+    }
+}
+
+/// Null-terminated strings, serialized as strings.
+pub(crate) mod cstring {
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+    use std::ffi::CString;
+
+    pub fn serialize<S: Serializer>(string: &CString, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&string.to_string_lossy())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CString, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        CString::new(string).map_err(de::Error::custom)
+    }
+}
+
+/// Lists of null-terminated strings, serialized as lists of strings.
+pub(crate) mod cstrings {
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+    use std::borrow::Cow;
+    use std::ffi::CString;
+
+    pub fn serialize<S: Serializer>(strings: &[CString], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(strings.iter().map(|string| string.to_string_lossy()))
+    }
+
+    pub fn deserialize<'de, 'a, D>(deserializer: D) -> Result<Cow<'a, [CString]>, D::Error>
+        where
+            D: Deserializer<'de>,
+    {
+        let strings = Vec::<String>::deserialize(deserializer)?;
+        strings
+            .into_iter()
+            .map(|string| CString::new(string).map_err(de::Error::custom))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Cow::Owned)
+    }
+}
+
+/// UUIDs formatted as ASCII strings, serialized as strings.
+pub(crate) mod uuid {
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S: Serializer>(uuid: &[u8; 36], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(uuid))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 36], D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let mut uuid = [0u8; 36];
+        if string.len() != uuid.len() {
+            return Err(de::Error::invalid_length(string.len(), &"a 36 byte UUID"));
+        }
+        uuid.copy_from_slice(string.as_bytes());
+        Ok(uuid)
+    }
+}
+
+#[test]
+fn test_serde_json_round_trip() {
+    use std::borrow::Cow;
+    use std::ffi::CString;
+
+    use crate::protocol::{msex, pinf};
+
+    let ploc = pinf::Message::new(pinf::PLoc {
+        listening_tcp_port: 4811,
+        kind: CString::new("MediaServer").unwrap(),
+        name: CString::new("Server").unwrap(),
+        state: CString::new("Running").unwrap(),
+    });
+    let json = serde_json::to_value(&ploc).unwrap();
+    assert_eq!(json["pinf_header"]["content_type"], "PLoc");
+    assert_eq!(json["pinf_header"]["citp_header"]["cookie"], "CITP");
+    assert_eq!(json["message"]["state"], "Running");
+    let read: pinf::Message<pinf::PLoc> = serde_json::from_value(json).unwrap();
+    assert_eq!(read, ploc);
+
+    let mut uuid = [0; 36];
+    uuid.copy_from_slice(b"0daf5c96-33f6-4d9c-8b45-a9c82b3c04b4");
+    let sinf = msex::SInf {
+        uuid,
+        product_name: Ucs2String::new("Server \u{1F3A5}").unwrap(),
+        product_version_major: 1,
+        product_version_minor: 2,
+        product_version_bugfix: 3,
+        supported_msex_versions: Cow::Borrowed(&[msex::Version::V1_2]),
+        supported_library_types: 1,
+        thumbnail_formats: Cow::Owned(vec![Cookie::new(msex::IMAGE_FORMAT_JPEG)]),
+        stream_formats: Cow::Borrowed(&[]),
+        layer_dmx_sources: Cow::Owned(vec![CString::new("ArtNet/0/0/1").unwrap()]),
+    };
+    let json = serde_json::to_string(&sinf).unwrap();
+    assert!(json.contains("\"uuid\":\"0daf5c96-33f6-4d9c-8b45-a9c82b3c04b4\""));
+    assert!(json.contains("\"product_name\":\"Server \u{1F3A5}\""));
+    assert!(json.contains("\"thumbnail_formats\":[\"JPEG\"]"));
+    assert!(json.contains("\"layer_dmx_sources\":[\"ArtNet/0/0/1\"]"));
+    assert_eq!(serde_json::from_str::<msex::SInf>(&json).unwrap(), sinf);
+
+    let cookie = Cookie::new(b"A\0\xFFB");
+    let json = serde_json::to_string(&cookie).unwrap();
+    assert_eq!(serde_json::from_str::<Cookie>(&json).unwrap(), cookie);
+    assert!(serde_json::from_str::<Cookie>("\"PLocs\"").is_err());
+    assert!(serde_json::from_str::<Cookie>("\"PL\u{100}c\"").is_err());
+}
@@ -0,0 +1,971 @@
+//! Property tests that write arbitrary values of every protocol type to bytes and read them back.
+//!
+//! Each test checks that the value read is equal to the value written and that `size_bytes`
+//! agrees with the number of bytes written. **Packet**s, which have no `size_bytes`, are only
+//! checked for equality.
+
+extern crate citp;
+extern crate proptest;
+
+use citp::protocol::msex::{
+    ReadFromBytesVersioned, SizeBytesVersioned, Version, WriteToBytesVersioned,
+};
+use citp::protocol::{self, finf, fptc, fsel, msex, pinf, sdmx};
+use citp::protocol::{ContentType, Cookie, Kind, Packet, ReadBytes, ReadFromBytes, SizeBytes};
+use citp::protocol::{Ucs2String, WriteBytes, WriteToBytes};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+use proptest::strategy::BoxedStrategy;
+use proptest::test_runner::TestCaseError;
+use std::borrow::Cow;
+use std::ffi::CString;
+use std::fmt::Debug;
+use std::num::NonZeroU16;
+
+const VERSIONS: &[Version] = &[Version::V1_0, Version::V1_1, Version::V1_2];
+
+/// Write the value, check its size and check that reading it back produces an equal value.
+fn round_trip<T>(value: &T) -> Result<(), TestCaseError>
+    where
+        T: WriteToBytes + ReadFromBytes + SizeBytes + PartialEq + Debug,
+{
+    let mut bytes = vec![];
+    bytes.write_bytes(value).unwrap();
+    prop_assert_eq!(value.size_bytes(), bytes.len());
+    let read: T = (&bytes[..]).read_bytes().unwrap();
+    prop_assert_eq!(&read, value);
+    Ok(())
+}
+
+/// The same as **round_trip** for MSEX types using the layout of the given version.
+fn round_trip_versioned<T>(value: &T, version: Version) -> Result<(), TestCaseError>
+    where
+        T: WriteToBytesVersioned + ReadFromBytesVersioned + SizeBytesVersioned + PartialEq + Debug,
+{
+    let mut bytes = vec![];
+    value.write_to_bytes_versioned(&mut bytes, version).unwrap();
+    prop_assert_eq!(value.size_bytes_versioned(version), bytes.len());
+    let read = T::read_from_bytes_versioned(&bytes[..], version).unwrap();
+    prop_assert_eq!(&read, value);
+    Ok(())
+}
+
+/// Round trip the MSEX message both on its own and wrapped within an MSEX **Message**.
+fn round_trip_msex<T>(value: T, version: Version) -> Result<(), TestCaseError>
+    where
+        T: ContentType
+            + WriteToBytesVersioned
+            + ReadFromBytesVersioned
+            + SizeBytesVersioned
+            + PartialEq
+            + Debug,
+{
+    round_trip_versioned(&value, version)?;
+    round_trip(&msex::Message::new(version, value))
+}
+
+/// Write the packet and check that reading it back produces an equal packet.
+fn round_trip_packet(packet: &Packet) -> Result<(), TestCaseError> {
+    let mut bytes = vec![];
+    bytes.write_bytes(packet).unwrap();
+    let read: Packet = (&bytes[..]).read_bytes().unwrap();
+    prop_assert_eq!(&read, packet);
+    Ok(())
+}
+
+/// Produces the strategy for each of the given MSEX versions, paired with the version.
+fn versioned<S, F>(
+    versions: &'static [Version],
+    strategy: F,
+) -> impl Strategy<Value = (Version, S::Value)>
+    where
+        S: Strategy,
+        F: Fn(Version) -> S,
+{
+    select(versions).prop_flat_map(move |version| (Just(version), strategy(version)))
+}
+
+/// Produces the default value unless the field is present, e.g. within a specific MSEX version.
+fn present_if<S>(present: bool, strategy: S) -> BoxedStrategy<S::Value>
+    where
+        S: Strategy + 'static,
+        S::Value: Clone + Default,
+{
+    match present {
+        true => strategy.boxed(),
+        false => Just(S::Value::default()).boxed(),
+    }
+}
+
+fn cstring() -> impl Strategy<Value = CString> {
+    vec(1u8.., 0..16).prop_map(|bytes| CString::new(bytes).unwrap())
+}
+
+fn ucs2_string() -> impl Strategy<Value = Ucs2String> {
+    vec(1u16.., 0..16).prop_map(|units| Ucs2String::from_units(units).unwrap())
+}
+
+fn cookie() -> impl Strategy<Value = Cookie> {
+    any::<[u8; 4]>().prop_map(Cookie::from)
+}
+
+fn request_index() -> impl Strategy<Value = NonZeroU16> {
+    (1u16..).prop_map(|index| NonZeroU16::new(index).unwrap())
+}
+
+/// The kind of a request or of a message that is not a response.
+fn request_kind() -> impl Strategy<Value = Kind> {
+    prop_oneof![Just(Kind::Ignored), request_index().prop_map(Kind::RequestIndex)]
+}
+
+/// The kind of an MSEX response.
+fn response_kind() -> impl Strategy<Value = Kind> {
+    prop_oneof![Just(Kind::Ignored), request_index().prop_map(Kind::InResponseTo)]
+}
+
+/// A UUID formatted as an ASCII string, zeroed unless the field is present.
+fn uuid(present: bool) -> BoxedStrategy<[u8; 36]> {
+    match present {
+        true => vec(any::<u8>(), 36)
+            .prop_map(|bytes| {
+                let mut uuid = [0; 36];
+                uuid.copy_from_slice(&bytes);
+                uuid
+            })
+            .boxed(),
+        false => Just([0; 36]).boxed(),
+    }
+}
+
+fn bytes() -> impl Strategy<Value = Cow<'static, [u8]>> {
+    vec(any::<u8>(), 0..64).prop_map(Cow::Owned)
+}
+
+fn fixture_identifiers() -> impl Strategy<Value = Cow<'static, [u16]>> {
+    vec(any::<u16>(), 0..16).prop_map(Cow::Owned)
+}
+
+// CITP
+
+fn citp_header() -> impl Strategy<Value = protocol::Header> {
+    let part = (any::<u32>(), any::<u16>(), any::<u16>());
+    (cookie(), any::<(u8, u8)>(), request_kind(), part, cookie()).prop_map(
+        |(cookie, (version_major, version_minor), kind, part, content_type)| {
+            let (message_size, message_part_count, message_part) = part;
+            protocol::Header {
+                cookie,
+                version_major,
+                version_minor,
+                kind,
+                message_size,
+                message_part_count,
+                message_part,
+                content_type,
+            }
+        },
+    )
+}
+
+/// A packet whose layer is not known, consisting of the CITP header followed by the payload.
+fn unknown_packet() -> impl Strategy<Value = Packet> {
+    const LAYERS: &[&[u8; 4]] = &[
+        pinf::Header::CONTENT_TYPE,
+        sdmx::Header::CONTENT_TYPE,
+        fptc::Header::CONTENT_TYPE,
+        fsel::Header::CONTENT_TYPE,
+        finf::Header::CONTENT_TYPE,
+        msex::Header::CONTENT_TYPE,
+    ];
+    let content_type = cookie().prop_filter("known layer", |ct| !LAYERS.iter().any(|&l| *ct == l));
+    (content_type, request_kind(), bytes()).prop_map(|(content_type, kind, payload)| {
+        let mut citp_header = protocol::Header::new(content_type, kind, 0);
+        citp_header.message_size = (citp_header.size_bytes() + payload.len()) as u32;
+        let mut bytes = vec![];
+        bytes.write_bytes(citp_header).unwrap();
+        bytes.extend_from_slice(&payload);
+        Packet::Unknown { citp_header, bytes }
+    })
+}
+
+// PINF
+
+fn pinf_header() -> impl Strategy<Value = pinf::Header> {
+    (citp_header(), cookie())
+        .prop_map(|(citp_header, content_type)| pinf::Header { citp_header, content_type })
+}
+
+fn pnam() -> impl Strategy<Value = pinf::PNam> {
+    cstring().prop_map(|name| pinf::PNam { name })
+}
+
+fn ploc() -> impl Strategy<Value = pinf::PLoc> {
+    (any::<u16>(), cstring(), cstring(), cstring()).prop_map(
+        |(listening_tcp_port, kind, name, state)| pinf::PLoc {
+            listening_tcp_port,
+            kind,
+            name,
+            state,
+        },
+    )
+}
+
+// SDMX
+
+fn sdmx_header() -> impl Strategy<Value = sdmx::Header> {
+    (citp_header(), cookie())
+        .prop_map(|(citp_header, content_type)| sdmx::Header { citp_header, content_type })
+}
+
+fn capa() -> impl Strategy<Value = sdmx::Capa<'static>> {
+    vec(any::<u16>(), 0..16).prop_map(|capabilities| sdmx::Capa {
+        capabilities: Cow::Owned(capabilities),
+    })
+}
+
+fn unam() -> impl Strategy<Value = sdmx::UNam> {
+    (any::<u8>(), cstring()).prop_map(|(universe_index, universe_name)| sdmx::UNam {
+        universe_index,
+        universe_name,
+    })
+}
+
+fn enid() -> impl Strategy<Value = sdmx::EnId> {
+    cstring().prop_map(|identifier| sdmx::EnId { identifier })
+}
+
+fn chbk() -> impl Strategy<Value = sdmx::ChBk<'static>> {
+    (any::<u8>(), any::<u8>(), any::<u16>(), bytes()).prop_map(
+        |(blind, universe_index, first_channel, channel_levels)| sdmx::ChBk {
+            blind,
+            universe_index,
+            first_channel,
+            channel_levels,
+        },
+    )
+}
+
+fn channel_level() -> impl Strategy<Value = sdmx::ChannelLevel> {
+    (any::<u8>(), any::<u16>(), any::<u8>()).prop_map(
+        |(universe_index, channel, channel_level)| sdmx::ChannelLevel {
+            universe_index,
+            channel,
+            channel_level,
+        },
+    )
+}
+
+fn chls() -> impl Strategy<Value = sdmx::ChLs<'static>> {
+    vec(channel_level(), 0..16).prop_map(|channel_levels| sdmx::ChLs {
+        channel_levels: Cow::Owned(channel_levels),
+    })
+}
+
+fn sxsr() -> impl Strategy<Value = sdmx::SXSr> {
+    cstring().prop_map(|connection_string| sdmx::SXSr { connection_string })
+}
+
+fn sxus() -> impl Strategy<Value = sdmx::Sxus> {
+    (any::<u8>(), cstring()).prop_map(|(universe_index, connection_string)| sdmx::Sxus {
+        universe_index,
+        connection_string,
+    })
+}
+
+// FPTC
+
+fn fptc_header() -> impl Strategy<Value = fptc::Header> {
+    (citp_header(), cookie(), any::<u32>()).prop_map(|(citp_header, content_type, content_hint)| {
+        fptc::Header {
+            citp_header,
+            content_type,
+            content_hint,
+        }
+    })
+}
+
+fn ptch() -> impl Strategy<Value = fptc::Ptch> {
+    let ids = (any::<u16>(), any::<u8>(), any::<u8>(), any::<u16>(), any::<u16>());
+    (ids, cstring(), cstring()).prop_map(
+        |((fixture_identifier, universe, reserved, channel, channel_count), make, name)| {
+            fptc::Ptch {
+                fixture_identifier,
+                universe,
+                reserved,
+                channel,
+                channel_count,
+                fixture_make: make,
+                fixture_name: name,
+            }
+        },
+    )
+}
+
+fn uptc() -> impl Strategy<Value = fptc::UPtc<'static>> {
+    fixture_identifiers().prop_map(|fixture_identifiers| fptc::UPtc { fixture_identifiers })
+}
+
+fn sptc() -> impl Strategy<Value = fptc::SPtc<'static>> {
+    fixture_identifiers().prop_map(|fixture_identifiers| fptc::SPtc { fixture_identifiers })
+}
+
+// FSEL
+
+fn fsel_header() -> impl Strategy<Value = fsel::Header> {
+    (citp_header(), cookie())
+        .prop_map(|(citp_header, content_type)| fsel::Header { citp_header, content_type })
+}
+
+fn sele() -> impl Strategy<Value = fsel::Sele<'static>> {
+    (any::<u8>(), any::<u8>(), fixture_identifiers()).prop_map(
+        |(complete, reserved, fixture_identifiers)| fsel::Sele {
+            complete,
+            reserved,
+            fixture_identifiers,
+        },
+    )
+}
+
+fn dese() -> impl Strategy<Value = fsel::DeSe<'static>> {
+    fixture_identifiers().prop_map(|fixture_identifiers| fsel::DeSe { fixture_identifiers })
+}
+
+// FINF
+
+fn finf_header() -> impl Strategy<Value = finf::Header> {
+    (citp_header(), cookie())
+        .prop_map(|(citp_header, content_type)| finf::Header { citp_header, content_type })
+}
+
+fn sfra() -> impl Strategy<Value = finf::SFra<'static>> {
+    fixture_identifiers().prop_map(|fixture_identifiers| finf::SFra { fixture_identifiers })
+}
+
+fn fram() -> impl Strategy<Value = finf::Fram> {
+    (any::<u16>(), any::<u8>(), any::<u8>(), cstring()).prop_map(
+        |(fixture_identifier, frame_filter_count, frame_gobo_count, frame_names)| finf::Fram {
+            fixture_identifier,
+            frame_filter_count,
+            frame_gobo_count,
+            frame_names,
+        },
+    )
+}
+
+// MSEX
+
+fn version() -> impl Strategy<Value = Version> {
+    (any::<u8>(), any::<u8>()).prop_map(|(major, minor)| Version { major, minor })
+}
+
+/// An MSEX header whose `kind` matches the variant implied by its content type.
+fn msex_header() -> impl Strategy<Value = msex::Header> {
+    let responses = msex::Header::RESPONSE_CONTENT_TYPES.iter().map(|&ct| Cookie::new(ct));
+    let content_type = prop_oneof![cookie(), select(responses.collect::<Vec<_>>())];
+    (citp_header(), version(), content_type).prop_flat_map(|(citp_header, version, content_type)| {
+        let header = msex::Header {
+            citp_header,
+            version_major: version.major,
+            version_minor: version.minor,
+            content_type,
+        };
+        let kind = match header.is_response() {
+            true => response_kind().boxed(),
+            false => request_kind().boxed(),
+        };
+        kind.prop_map(move |kind| {
+            let mut header = header;
+            header.citp_header.kind = kind;
+            header
+        })
+    })
+}
+
+fn library_id() -> impl Strategy<Value = msex::LibraryId> {
+    any::<[u8; 4]>().prop_map(|[level, sub_level_1, sub_level_2, sub_level_3]| msex::LibraryId {
+        level,
+        sub_level_1,
+        sub_level_2,
+        sub_level_3,
+    })
+}
+
+/// A library number for MSEX 1.0 or a library ID for later versions.
+fn library(version: Version) -> impl Strategy<Value = (u8, msex::LibraryId)> {
+    let number = present_if(version == Version::V1_0, any::<u8>());
+    let id = present_if(version != Version::V1_0, library_id());
+    (number, id)
+}
+
+/// A count that may be written as a `u8` prior to MSEX 1.2 and a `u16` otherwise.
+fn count(version: Version) -> BoxedStrategy<u16> {
+    match version {
+        Version::V1_2 => any::<u16>().boxed(),
+        _ => (0..=u16::from(u8::MAX)).boxed(),
+    }
+}
+
+fn cinf() -> impl Strategy<Value = msex::CInf<'static>> {
+    (vec(version(), 0..8), bytes()).prop_map(|(versions, future_message_data)| msex::CInf {
+        supported_msex_versions: Cow::Owned(versions),
+        future_message_data,
+    })
+}
+
+fn sinf(version: Version) -> impl Strategy<Value = msex::SInf<'static>> {
+    let v1_2 = version == Version::V1_2;
+    let product = (ucs2_string(), any::<u8>(), any::<u8>());
+    let formats = (vec(cookie(), 0..8), vec(cookie(), 0..8));
+    let v1_2_fields = (any::<u8>(), vec(self::version(), 0..8), any::<u16>(), formats);
+    let v1_2_fields = present_if(v1_2, v1_2_fields);
+    (uuid(v1_2), product, v1_2_fields, vec(cstring(), 0..8)).prop_map(
+        |(uuid, (product_name, major, minor), v1_2_fields, layer_dmx_sources)| {
+            let (bugfix, versions, library_types, (thumbnail, stream)) = v1_2_fields;
+            msex::SInf {
+                uuid,
+                product_name,
+                product_version_major: major,
+                product_version_minor: minor,
+                product_version_bugfix: bugfix,
+                supported_msex_versions: Cow::Owned(versions),
+                supported_library_types: library_types,
+                thumbnail_formats: Cow::Owned(thumbnail),
+                stream_formats: Cow::Owned(stream),
+                layer_dmx_sources: Cow::Owned(layer_dmx_sources),
+            }
+        },
+    )
+}
+
+fn layer_status(version: Version) -> impl Strategy<Value = msex::LayerStatus> {
+    let v1_2 = version == Version::V1_2;
+    let media_library = (
+        present_if(!v1_2, any::<u8>()),
+        present_if(v1_2, any::<u8>()),
+        present_if(v1_2, library_id()),
+    );
+    let media = (any::<u8>(), ucs2_string(), any::<u32>(), any::<u32>(), any::<u8>());
+    (any::<u8>(), any::<u8>(), media_library, media, any::<u32>()).prop_map(
+        |(layer_number, physical_output, library, media, layer_status_flags)| {
+            let (media_library_number, media_library_type, media_library_id) = library;
+            let (media_number, media_name, media_position, media_length, media_fps) = media;
+            msex::LayerStatus {
+                layer_number,
+                physical_output,
+                media_library_number,
+                media_library_type,
+                media_library_id,
+                media_number,
+                media_name,
+                media_position,
+                media_length,
+                media_fps,
+                layer_status_flags,
+            }
+        },
+    )
+}
+
+fn lsta(version: Version) -> impl Strategy<Value = msex::LSta<'static>> {
+    vec(layer_status(version), 0..8).prop_map(|layer_statuses| msex::LSta {
+        layer_statuses: Cow::Owned(layer_statuses),
+    })
+}
+
+fn nack() -> impl Strategy<Value = msex::Nack> {
+    cookie().prop_map(|received_content_type| msex::Nack { received_content_type })
+}
+
+fn geli(version: Version) -> impl Strategy<Value = msex::GELI<'static>> {
+    let parent_id = present_if(version != Version::V1_0, library_id());
+    (any::<u8>(), parent_id, bytes()).prop_map(
+        |(library_type, library_parent_id, library_numbers)| msex::GELI {
+            library_type,
+            library_parent_id,
+            library_numbers,
+        },
+    )
+}
+
+fn library_information(version: Version) -> impl Strategy<Value = msex::LibraryInformation> {
+    let serial_number = present_if(version == Version::V1_2, any::<u32>());
+    let library_count = present_if(version != Version::V1_0, count(version));
+    let counts = (library_count, count(version));
+    let dmx_range = (any::<u8>(), any::<u8>());
+    (library(version), serial_number, dmx_range, ucs2_string(), counts).prop_map(
+        |((number, id), serial_number, (min, max), name, (library_count, element_count))| {
+            msex::LibraryInformation {
+                number,
+                id,
+                serial_number,
+                dmx_range_min: min,
+                dmx_range_max: max,
+                name,
+                library_count,
+                element_count,
+            }
+        },
+    )
+}
+
+fn elin(version: Version) -> impl Strategy<Value = msex::ELIn<'static>> {
+    (any::<u8>(), vec(library_information(version), 0..8)).prop_map(
+        |(library_type, elements)| msex::ELIn {
+            library_type,
+            elements: Cow::Owned(elements),
+        },
+    )
+}
+
+fn elup(version: Version) -> impl Strategy<Value = msex::ELUp> {
+    let v1_2 = version == Version::V1_2;
+    let affected = (present_if(v1_2, any::<[u8; 32]>()), present_if(v1_2, any::<[u8; 32]>()));
+    (any::<u8>(), library(version), any::<u8>(), affected).prop_map(
+        |(library_type, (library_number, library_id), update_flags, affected)| msex::ELUp {
+            library_type,
+            library_number,
+            library_id,
+            update_flags,
+            affected_elements: affected.0,
+            affected_libraries: affected.1,
+        },
+    )
+}
+
+fn gein(version: Version) -> impl Strategy<Value = msex::GEIn<'static>> {
+    (any::<u8>(), library(version), bytes()).prop_map(
+        |(library_type, (library_number, library_id), element_numbers)| msex::GEIn {
+            library_type,
+            library_number,
+            library_id,
+            element_numbers,
+        },
+    )
+}
+
+fn media_information(version: Version) -> impl Strategy<Value = msex::MediaInformation> {
+    let serial_number = present_if(version == Version::V1_2, any::<u32>());
+    let media = (any::<u64>(), any::<u16>(), any::<u16>(), any::<u32>(), any::<u8>());
+    (any::<u8>(), serial_number, any::<u8>(), any::<u8>(), ucs2_string(), media).prop_map(
+        |(number, serial_number, dmx_range_min, dmx_range_max, media_name, media)| {
+            let (timestamp, width, height, length, fps) = media;
+            msex::MediaInformation {
+                number,
+                serial_number,
+                dmx_range_min,
+                dmx_range_max,
+                media_name,
+                media_version_timestamp: timestamp,
+                media_width: width,
+                media_height: height,
+                media_length: length,
+                media_fps: fps,
+            }
+        },
+    )
+}
+
+fn mein(version: Version) -> impl Strategy<Value = msex::MEIn<'static>> {
+    (library(version), vec(media_information(version), 0..8)).prop_map(
+        |((library_number, library_id), elements)| msex::MEIn {
+            library_number,
+            library_id,
+            elements: Cow::Owned(elements),
+        },
+    )
+}
+
+fn effect_information(version: Version) -> impl Strategy<Value = msex::EffectInformation> {
+    let serial_number = present_if(version == Version::V1_2, any::<u32>());
+    let names = (ucs2_string(), vec(ucs2_string(), 0..8));
+    (any::<u8>(), serial_number, any::<u8>(), any::<u8>(), names).prop_map(
+        |(number, serial_number, dmx_range_min, dmx_range_max, names)| {
+            let (effect_name, effect_parameter_names) = names;
+            msex::EffectInformation {
+                number,
+                serial_number,
+                dmx_range_min,
+                dmx_range_max,
+                effect_name,
+                effect_parameter_names,
+            }
+        },
+    )
+}
+
+fn eein(version: Version) -> impl Strategy<Value = msex::EEIn<'static>> {
+    (library(version), vec(effect_information(version), 0..8)).prop_map(
+        |((library_number, library_id), elements)| msex::EEIn {
+            library_number,
+            library_id,
+            elements: Cow::Owned(elements),
+        },
+    )
+}
+
+fn generic_information(version: Version) -> impl Strategy<Value = msex::GenericInformation> {
+    let serial_number = present_if(version == Version::V1_2, any::<u32>());
+    let dmx_range = (any::<u8>(), any::<u8>());
+    (any::<u8>(), serial_number, dmx_range, ucs2_string(), any::<u64>()).prop_map(
+        |(number, serial_number, (min, max), name, version_timestamp)| {
+            msex::GenericInformation {
+                number,
+                serial_number,
+                dmx_range_min: min,
+                dmx_range_max: max,
+                name,
+                version_timestamp,
+            }
+        },
+    )
+}
+
+fn glei(version: Version) -> impl Strategy<Value = msex::GLEI<'static>> {
+    (library_id(), any::<u8>(), vec(generic_information(version), 0..8)).prop_map(
+        |(library_id, library_type, elements)| msex::GLEI {
+            library_id,
+            library_type,
+            elements: Cow::Owned(elements),
+        },
+    )
+}
+
+/// The thumbnail format, width and height.
+fn thumbnail() -> impl Strategy<Value = (Cookie, u16, u16)> {
+    (cookie(), any::<u16>(), any::<u16>())
+}
+
+fn gelt(version: Version) -> impl Strategy<Value = msex::GELT<'static>> {
+    let v1_0 = version == Version::V1_0;
+    let libraries = (present_if(v1_0, bytes()), present_if(!v1_0, vec(library_id(), 0..8)));
+    (thumbnail(), any::<u8>(), any::<u8>(), libraries).prop_map(
+        |((format, width, height), flags, library_type, (numbers, ids))| msex::GELT {
+            thumbnail_format: format,
+            thumbnail_width: width,
+            thumbnail_height: height,
+            thumbnail_flags: flags,
+            library_type,
+            library_numbers: numbers,
+            library_ids: Cow::Owned(ids),
+        },
+    )
+}
+
+fn elth(version: Version) -> impl Strategy<Value = msex::ELTh<'static>> {
+    (any::<u8>(), library(version), thumbnail(), bytes()).prop_map(
+        |(library_type, (library_number, library_id), (format, width, height), buffer)| {
+            msex::ELTh {
+                library_type,
+                library_number,
+                library_id,
+                thumbnail_format: format,
+                thumbnail_width: width,
+                thumbnail_height: height,
+                thumbnail_buffer: buffer,
+            }
+        },
+    )
+}
+
+fn geth(version: Version) -> impl Strategy<Value = msex::GETh<'static>> {
+    let library = (any::<u8>(), library(version));
+    (thumbnail(), any::<u8>(), library, bytes()).prop_map(
+        |((format, width, height), flags, (library_type, (number, id)), element_numbers)| {
+            msex::GETh {
+                thumbnail_format: format,
+                thumbnail_width: width,
+                thumbnail_height: height,
+                thumbnail_flags: flags,
+                library_type,
+                library_number: number,
+                library_id: id,
+                element_numbers,
+            }
+        },
+    )
+}
+
+fn ethn(version: Version) -> impl Strategy<Value = msex::EThn<'static>> {
+    let library = (any::<u8>(), library(version));
+    (library, any::<u8>(), thumbnail(), bytes()).prop_map(
+        |((library_type, (number, id)), element_number, (format, width, height), buffer)| {
+            msex::EThn {
+                library_type,
+                library_number: number,
+                library_id: id,
+                element_number,
+                thumbnail_format: format,
+                thumbnail_width: width,
+                thumbnail_height: height,
+                thumbnail_buffer: buffer,
+            }
+        },
+    )
+}
+
+fn source_information() -> impl Strategy<Value = msex::SourceInformation> {
+    let output = (any::<u8>(), any::<u8>(), any::<u16>(), any::<u16>(), any::<u16>());
+    (any::<u16>(), ucs2_string(), output).prop_map(
+        |(source_identifier, source_name, output)| {
+            let (physical_output, layer_number, flags, width, height) = output;
+            msex::SourceInformation {
+                source_identifier,
+                source_name,
+                physical_output,
+                layer_number,
+                flags,
+                width,
+                height,
+            }
+        },
+    )
+}
+
+fn vsrc() -> impl Strategy<Value = msex::VSrc<'static>> {
+    vec(source_information(), 0..8).prop_map(|sources| msex::VSrc {
+        sources: Cow::Owned(sources),
+    })
+}
+
+fn rqst() -> impl Strategy<Value = msex::RqSt> {
+    let frame = (cookie(), any::<u16>(), any::<u16>());
+    (any::<u16>(), frame, any::<u8>(), any::<u8>()).prop_map(
+        |(source_identifier, (frame_format, frame_width, frame_height), fps, timeout)| {
+            msex::RqSt {
+                source_identifier,
+                frame_format,
+                frame_width,
+                frame_height,
+                fps,
+                timeout,
+            }
+        },
+    )
+}
+
+fn fragment() -> impl Strategy<Value = msex::Fragment> {
+    (any::<u32>(), any::<u16>(), any::<u16>(), any::<u32>()).prop_map(
+        |(frame_index, fragment_count, fragment_index, fragment_byte_offset)| msex::Fragment {
+            frame_index,
+            fragment_count,
+            fragment_index,
+            fragment_byte_offset,
+        },
+    )
+}
+
+fn stfr(version: Version) -> impl Strategy<Value = msex::StFr<'static>> {
+    let frame_format = prop_oneof![
+        cookie(),
+        Just(Cookie::new(msex::IMAGE_FORMAT_FRAGMENTED_JPEG)),
+        Just(Cookie::new(msex::IMAGE_FORMAT_FRAGMENTED_PNG)),
+    ];
+    let v1_2 = version == Version::V1_2;
+    let frame = frame_format.prop_flat_map(move |format| {
+        let fragmented = v1_2 && msex::is_fragmented_format(format);
+        let fragment = present_if(fragmented, fragment().prop_map(Some));
+        (Just(format), any::<u16>(), any::<u16>(), fragment)
+    });
+    (uuid(v1_2), any::<u16>(), frame, bytes()).prop_map(
+        |(media_server_uuid, source_identifier, frame, frame_buffer)| {
+            let (frame_format, frame_width, frame_height, fragment) = frame;
+            msex::StFr {
+                media_server_uuid,
+                source_identifier,
+                frame_format,
+                frame_width,
+                frame_height,
+                fragment,
+                frame_buffer,
+            }
+        },
+    )
+}
+
+proptest! {
+    #[test]
+    fn test_header_round_trip(
+        citp_header in citp_header(),
+        pinf_header in pinf_header(),
+        sdmx_header in sdmx_header(),
+        fptc_header in fptc_header(),
+        fsel_header in fsel_header(),
+        finf_header in finf_header(),
+        msex_header in msex_header(),
+    ) {
+        round_trip(&citp_header)?;
+        round_trip(&pinf_header)?;
+        round_trip(&sdmx_header)?;
+        round_trip(&fptc_header)?;
+        round_trip(&fsel_header)?;
+        round_trip(&finf_header)?;
+        round_trip(&msex_header)?;
+    }
+
+    #[test]
+    fn test_packet_round_trip(
+        (ploc, chls, ptch, sele, fram) in (ploc(), chls(), ptch(), sele(), fram()),
+        (version, (geli, elin)) in versioned(VERSIONS, |v| (geli(v), elin(v))),
+        (request, response) in (request_kind(), response_kind()),
+        unknown in unknown_packet(),
+    ) {
+        let mut ploc = pinf::Message::new(ploc);
+        ploc.pinf_header.citp_header.kind = request;
+        let mut geli = msex::Message::new(version, geli);
+        geli.msex_header.citp_header.kind = request;
+        let mut elin = msex::Message::new(version, elin);
+        elin.msex_header.citp_header.kind = response;
+        round_trip_packet(&Packet::PLoc(ploc))?;
+        round_trip_packet(&Packet::ChLs(sdmx::Message::new(chls)))?;
+        round_trip_packet(&Packet::Ptch(fptc::Message::new(ptch)))?;
+        round_trip_packet(&Packet::Sele(fsel::Message::new(sele)))?;
+        round_trip_packet(&Packet::Fram(finf::Message::new(fram)))?;
+        round_trip_packet(&Packet::GELI(geli))?;
+        round_trip_packet(&Packet::ELIn(elin))?;
+        round_trip_packet(&unknown)?;
+    }
+
+    #[test]
+    fn test_pinf_round_trip(pnam in pnam(), ploc in ploc()) {
+        round_trip(&pinf::Message::new(pnam))?;
+        round_trip(&pinf::Message::new(ploc))?;
+    }
+
+    #[test]
+    fn test_sdmx_round_trip(
+        capa in capa(),
+        unam in unam(),
+        enid in enid(),
+        chbk in chbk(),
+        chls in chls(),
+        sxsr in sxsr(),
+        sxus in sxus(),
+    ) {
+        for channel_level in chls.channel_levels.iter() {
+            round_trip(channel_level)?;
+        }
+        round_trip(&sdmx::Message::new(capa))?;
+        round_trip(&sdmx::Message::new(unam))?;
+        round_trip(&sdmx::Message::new(enid))?;
+        round_trip(&sdmx::Message::new(chbk))?;
+        round_trip(&sdmx::Message::new(chls))?;
+        round_trip(&sdmx::Message::new(sxsr))?;
+        round_trip(&sdmx::Message::new(sxus))?;
+    }
+
+    #[test]
+    fn test_fptc_round_trip(ptch in ptch(), uptc in uptc(), sptc in sptc()) {
+        round_trip(&fptc::Message::new(ptch))?;
+        round_trip(&fptc::Message::new(uptc))?;
+        round_trip(&fptc::Message::new(sptc))?;
+    }
+
+    #[test]
+    fn test_fsel_round_trip(sele in sele(), dese in dese()) {
+        round_trip(&fsel::Message::new(sele))?;
+        round_trip(&fsel::Message::new(dese))?;
+    }
+
+    #[test]
+    fn test_finf_round_trip(sfra in sfra(), fram in fram()) {
+        round_trip(&finf::Message::new(sfra))?;
+        round_trip(&finf::Message::new(fram))?;
+    }
+
+    #[test]
+    fn test_msex_unversioned_round_trip(
+        version in version(),
+        library_id in library_id(),
+        source_information in source_information(),
+        rqst in rqst(),
+        fragment in fragment(),
+    ) {
+        round_trip(&version)?;
+        round_trip(&library_id)?;
+        round_trip(&source_information)?;
+        round_trip(&rqst)?;
+        round_trip(&fragment)?;
+    }
+
+    #[test]
+    fn test_msex_cinf_sinf_round_trip(
+        cinf in cinf(),
+        (version, sinf) in versioned(VERSIONS, sinf),
+    ) {
+        round_trip_msex(cinf, version)?;
+        round_trip_msex(sinf, version)?;
+    }
+
+    #[test]
+    fn test_msex_lsta_nack_round_trip(
+        (version, lsta) in versioned(VERSIONS, lsta),
+        nack in nack(),
+    ) {
+        for layer_status in lsta.layer_statuses.iter() {
+            round_trip_versioned(layer_status, version)?;
+        }
+        round_trip_msex(lsta, version)?;
+        round_trip_msex(nack, Version::V1_2)?;
+    }
+
+    #[test]
+    fn test_msex_library_round_trip(
+        (version, (geli, elin, elup)) in versioned(VERSIONS, |v| (geli(v), elin(v), elup(v))),
+    ) {
+        for element in elin.elements.iter() {
+            round_trip_versioned(element, version)?;
+        }
+        round_trip_msex(geli, version)?;
+        round_trip_msex(elin, version)?;
+        round_trip_msex(elup, version)?;
+    }
+
+    #[test]
+    fn test_msex_element_round_trip(
+        (version, (gein, mein, eein)) in versioned(VERSIONS, |v| (gein(v), mein(v), eein(v))),
+    ) {
+        for element in mein.elements.iter() {
+            round_trip_versioned(element, version)?;
+        }
+        for element in eein.elements.iter() {
+            round_trip_versioned(element, version)?;
+        }
+        round_trip_msex(gein, version)?;
+        round_trip_msex(mein, version)?;
+        round_trip_msex(eein, version)?;
+    }
+
+    #[test]
+    fn test_msex_glei_round_trip(
+        (version, glei) in versioned(&[Version::V1_1, Version::V1_2], glei),
+    ) {
+        for element in glei.elements.iter() {
+            round_trip_versioned(element, version)?;
+        }
+        round_trip_msex(glei, version)?;
+    }
+
+    #[test]
+    fn test_msex_thumbnail_round_trip(
+        (version, (gelt, elth, geth, ethn)) in
+            versioned(VERSIONS, |v| (gelt(v), elth(v), geth(v), ethn(v))),
+    ) {
+        round_trip_msex(gelt, version)?;
+        round_trip_msex(elth, version)?;
+        round_trip_msex(geth, version)?;
+        round_trip_msex(ethn, version)?;
+    }
+
+    #[test]
+    fn test_msex_video_round_trip(
+        vsrc in vsrc(),
+        rqst in rqst(),
+        (version, stfr) in versioned(VERSIONS, stfr),
+    ) {
+        round_trip_msex(msex::GVSr, version)?;
+        round_trip_msex(vsrc, version)?;
+        round_trip_msex(rqst, version)?;
+        round_trip_msex(stfr, version)?;
+    }
+}
+